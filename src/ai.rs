@@ -1,14 +1,53 @@
 use reqwest::blocking::Client;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Local Ollama text generation, used to interpret commands and to summarize.
+pub const GENERATE_URL: &str = "http://localhost:11434/api/generate";
+pub const GENERATE_MODEL: &str = "llama3:8b";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct AiAction {
-    pub intent: String,            // "search", "open", "summarize", "delete", etc.
+    pub intent: String,            // "search", "open", "summarize", "delete", "move", etc.
     pub query: Option<String>,     // text extracted from user input
     pub file_type: Option<String>, // "pdf", "image", "text", etc.
     pub time_filter: Option<String>, // "yesterday", "last week", etc.
     pub folder_hint: Option<String>, // "downloads", "documents", etc.
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub min_size: Option<String>, // "1GB", "500 KB", or bytes
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub max_size: Option<String>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub extensions: Option<Vec<String>>, // ["csv", "tsv"]
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub include_globs: Option<Vec<String>>, // ["projects/**"]
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub exclude_globs: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub destination: Option<String>, // folder for move/copy/organize, new name for rename
+}
+
+/// Small models emit `1073741824`, `"1GB"` or `null` for the same field.
+fn lenient_string<'de, D: Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    Ok(match Value::deserialize(d)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+/// Accept a JSON list or a single comma-separated string.
+fn lenient_list<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Vec<String>>, D::Error> {
+    let items: Vec<String> = match Value::deserialize(d)? {
+        Value::String(s) => s.split(',').map(|p| p.trim().to_string()).collect(),
+        Value::Array(xs) => xs
+            .into_iter()
+            .filter_map(|x| x.as_str().map(|s| s.trim().to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let items: Vec<String> = items.into_iter().filter(|s| !s.is_empty()).collect();
+    Ok((!items.is_empty()).then_some(items))
 }
 
 pub fn interpret_command(input: &str) -> anyhow::Result<AiAction> {
@@ -20,11 +59,19 @@ pub fn interpret_command(input: &str) -> anyhow::Result<AiAction> {
 User input: "{input}"
 
 Extract:
-- intent (one of: search, open, read, summarize, delete)
+- intent (one of: search, open, read, summarize, delete, move, copy, rename, organize;
+  "organize" moves every file matching the filters into a folder)
 - query (keywords to search for)
 - file_type (optional)
-- time_filter (optional)
+- time_filter (optional, a short phrase such as "yesterday", "last week", "2 days ago",
+  "since monday", "in march" or "before 2024-03-01"; start it with "created" or "opened"
+  when the user asks about creation or last access rather than modification)
 - folder_hint (optional)
+- min_size / max_size (optional, e.g. "1GB", "500KB")
+- extensions (optional list without dots, e.g. ["csv"])
+- include_globs / exclude_globs (optional lists of path globs, e.g. ["projects/**"])
+- destination (for move/copy/organize the target folder, e.g. "Documents/receipts";
+  for rename the new file name)
 
 Respond in JSON ONLY. Example:
 {{
@@ -33,17 +80,33 @@ Respond in JSON ONLY. Example:
   "file_type": "pdf",
   "time_filter": "yesterday",
   "folder_hint": "downloads"
+}}
+Another example, for "big videos over 1GB in Downloads, not in old/":
+{{
+  "intent": "search",
+  "query": "videos",
+  "file_type": "video",
+  "folder_hint": "downloads",
+  "min_size": "1GB",
+  "exclude_globs": ["old/**"]
+}}
+Another example, for "put all the PDFs from yesterday into Documents/receipts":
+{{
+  "intent": "organize",
+  "file_type": "pdf",
+  "time_filter": "yesterday",
+  "destination": "Documents/receipts"
 }}"#
     );
 
     let body = json!({
-        "model": "llama3:8b",
+        "model": GENERATE_MODEL,
         "prompt": prompt,
         "stream": false
     });
 
     let response = client
-        .post("http://localhost:11434/api/generate")
+        .post(GENERATE_URL)
         .json(&body)
         .send()?
         .json::<serde_json::Value>()?;
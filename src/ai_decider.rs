@@ -78,10 +78,11 @@ pub fn decide_best(query: &str, candidates: &[Candidate]) -> Result<Option<usize
         return Ok(None);
     }
 
-    if let Some(choice) = parsed.choice {
-        if choice >= 1 && choice <= candidates.len() {
-            return Ok(Some(choice));
-        }
+    if let Some(choice) = parsed.choice
+        && choice >= 1
+        && choice <= candidates.len()
+    {
+        return Ok(Some(choice));
     }
 
     Ok(None)
@@ -0,0 +1,286 @@
+use crate::similarity::cosine_similarity;
+use crate::vector_db::VectorDB;
+use std::thread;
+
+/// Below this many vectors brute force is fast enough; don't bother clustering.
+const MIN_VECTORS_FOR_IVF: usize = 5000;
+
+/// Retrain once the collection has grown this much since the last training.
+const RETRAIN_GROWTH: usize = 4;
+
+const KMEANS_ITERS: usize = 8;
+
+/// Training samples per centroid.
+const SAMPLES_PER_CENTROID: usize = 40;
+
+/// Vectors assigned per DB round trip.
+const ASSIGN_BATCH: usize = 5000;
+
+/// Inverted file (IVF) index for approximate nearest-neighbour search.
+///
+/// Vectors are grouped around k-means centroids stored in the DB; a query only
+/// loads the vectors in the few clusters closest to it instead of the whole table.
+pub struct IvfIndex {
+    centroids: Vec<Vec<f32>>,
+}
+
+impl IvfIndex {
+    /// Load the trained centroids, if the collection has been clustered.
+    pub fn load(db: &VectorDB) -> anyhow::Result<Option<Self>> {
+        let centroids = db.load_centroids()?;
+        if centroids.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self { centroids }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.centroids.len()
+    }
+
+    /// Ids of the `nprobe` clusters closest to `query`.
+    pub fn probe(&self, query: &[f32], nprobe: usize) -> Vec<i64> {
+        let mut scored: Vec<(usize, f32)> = self
+            .centroids
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i, cosine_similarity(query, c)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        scored
+            .into_iter()
+            .take(nprobe.max(1))
+            .map(|(i, _)| i as i64)
+            .collect()
+    }
+
+    fn nearest(&self, v: &[f32]) -> usize {
+        nearest(&self.centroids, v)
+    }
+}
+
+/// Keep the IVF index in step with the vectors table: train (or retrain)
+/// centroids once the collection is big enough, then file every new vector
+/// under its nearest centroid.
+pub fn maintain(db: &VectorDB) -> anyhow::Result<()> {
+    let total = db.count_vectors()?;
+    let trained_on: usize = db
+        .get_meta("ivf_trained_on")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    let needs_training = total >= MIN_VECTORS_FOR_IVF
+        && (trained_on == 0 || total >= trained_on * RETRAIN_GROWTH);
+
+    if needs_training {
+        let k = ((total as f64).sqrt() as usize).clamp(16, 256);
+        println!("Training approximate index ({} clusters over {} vectors)…", k, total);
+
+        let sample = db.sample_vectors(k * SAMPLES_PER_CENTROID)?;
+        let centroids = kmeans(&sample, k, KMEANS_ITERS);
+        db.store_centroids(&centroids)?;
+        db.set_meta("ivf_trained_on", &total.to_string())?;
+    }
+
+    assign_unclustered(db)
+}
+
+/// File every vector that has no cluster yet under its nearest centroid.
+pub fn assign_unclustered(db: &VectorDB) -> anyhow::Result<()> {
+    let Some(index) = IvfIndex::load(db)? else {
+        return Ok(());
+    };
+
+    loop {
+        let batch = db.load_unclustered(ASSIGN_BATCH)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let clusters = parallel_map(&batch, |(_, _, v)| index.nearest(v) as i64);
+        let assignments: Vec<(&str, i64, i64)> = batch
+            .iter()
+            .zip(clusters)
+            .map(|((table, rowid, _), cluster)| (*table, *rowid, cluster))
+            .collect();
+
+        db.set_clusters(&assignments)?;
+    }
+}
+
+/// Spherical k-means (cosine), seeded with evenly spaced samples.
+fn kmeans(samples: &[Vec<f32>], k: usize, iters: usize) -> Vec<Vec<f32>> {
+    let Some(dim) = samples.first().map(|v| v.len()) else {
+        return Vec::new();
+    };
+    let k = k.min(samples.len()).max(1);
+
+    let step = samples.len() / k;
+    let mut centroids: Vec<Vec<f32>> = (0..k).map(|i| normalized(&samples[i * step])).collect();
+
+    for _ in 0..iters {
+        let assignment = parallel_map(samples, |v| nearest(&centroids, v));
+
+        let mut sums = vec![vec![0.0f32; dim]; k];
+        let mut counts = vec![0usize; k];
+        for (v, c) in samples.iter().zip(&assignment) {
+            counts[*c] += 1;
+            for (s, x) in sums[*c].iter_mut().zip(v) {
+                *s += x;
+            }
+        }
+
+        for (i, sum) in sums.into_iter().enumerate() {
+            // Empty clusters keep their previous centroid
+            if counts[i] > 0 {
+                centroids[i] = normalized(&sum);
+            }
+        }
+    }
+
+    centroids
+}
+
+fn nearest(centroids: &[Vec<f32>], v: &[f32]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, cosine_similarity(v, c)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn normalized(v: &[f32]) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return v.to_vec();
+    }
+    v.iter().map(|x| x / norm).collect()
+}
+
+/// Map over `items` on all cores, keeping order.
+fn parallel_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let per_thread = items.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(per_thread)
+            .map(|part| scope.spawn(|| part.iter().map(&f).collect::<Vec<R>>()))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("worker thread panicked"))
+            .collect()
+    })
+}
+
+/// Measure recall@k of the approximate search against exact search, using
+/// randomly sampled stored vectors as queries. Returns the mean recall.
+pub fn bench_recall(db: &VectorDB, queries: usize, k: usize, nprobe: usize) -> anyhow::Result<f32> {
+    let Some(index) = IvfIndex::load(db)? else {
+        anyhow::bail!("No approximate index yet (needs at least {} vectors)", MIN_VECTORS_FOR_IVF);
+    };
+
+    let all = db.load_all(None)?;
+    let samples = db.sample_vectors(queries)?;
+    if samples.is_empty() {
+        anyhow::bail!("Index is empty");
+    }
+
+    let top_k = |candidates: &[(String, Vec<f32>)], q: &[f32]| -> Vec<String> {
+        let mut scored: Vec<(&String, f32)> = candidates
+            .iter()
+            .map(|(p, v)| (p, cosine_similarity(q, v)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().take(k).map(|(p, _)| p.clone()).collect()
+    };
+
+    let mut total_recall = 0.0f32;
+    for q in &samples {
+        let exact = top_k(&all, q);
+        let approx_rows = db.load_all(Some(&index.probe(q, nprobe)))?;
+        let approx = top_k(&approx_rows, q);
+
+        let hits = exact.iter().filter(|p| approx.contains(p)).count();
+        total_recall += hits as f32 / exact.len().max(1) as f32;
+    }
+
+    Ok(total_recall / samples.len() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SearchOptions;
+    use crate::vector_db::FileState;
+
+    /// Small deterministic generator (xorshift), so the tests need no `rand`.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> f32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % 10_000) as f32 / 10_000.0 - 0.5
+        }
+
+        fn vector(&mut self, dim: usize) -> Vec<f32> {
+            (0..dim).map(|_| self.next()).collect()
+        }
+    }
+
+    /// `per_cluster` noisy points around each of `clusters` random centres.
+    fn clustered(rng: &mut Rng, clusters: usize, per_cluster: usize, dim: usize) -> Vec<Vec<f32>> {
+        let centres: Vec<Vec<f32>> = (0..clusters).map(|_| rng.vector(dim)).collect();
+        let mut points = Vec::new();
+        for _ in 0..per_cluster {
+            for centre in &centres {
+                let noise = rng.vector(dim);
+                points.push(centre.iter().zip(noise).map(|(c, n)| c + 0.15 * n).collect());
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn kmeans_separates_clusters_deterministically() {
+        let mut rng = Rng(42);
+        let samples = clustered(&mut rng, 4, 50, 16);
+
+        let centroids = kmeans(&samples, 4, KMEANS_ITERS);
+        assert_eq!(centroids.len(), 4);
+        assert_eq!(centroids, kmeans(&samples, 4, KMEANS_ITERS));
+
+        // Points are interleaved by cluster: sample i belongs to cluster i % 4
+        let assigned: Vec<usize> = samples.iter().map(|v| nearest(&centroids, v)).collect();
+        for (i, c) in assigned.iter().enumerate() {
+            assert_eq!(*c, assigned[i % 4], "sample {} left its cluster", i);
+        }
+        let mut distinct = assigned[..4].to_vec();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 4);
+    }
+
+    #[test]
+    fn ivf_recall_at_10_on_clustered_vectors() {
+        let db = VectorDB::new(":memory:").unwrap();
+        let mut rng = Rng(7);
+        let state = FileState { modified: 0, size: 0 };
+        for (i, v) in clustered(&mut rng, 40, 150, 32).iter().enumerate() {
+            db.store_embedding(&format!("/synthetic/{}", i), v, state, "").unwrap();
+        }
+
+        maintain(&db).unwrap();
+        assert!(IvfIndex::load(&db).unwrap().is_some(), "IVF index was not trained");
+
+        let recall = bench_recall(&db, 50, 10, SearchOptions::default().nprobe).unwrap();
+        assert!(recall >= 0.9, "recall@10 was {}", recall);
+    }
+}
@@ -0,0 +1,59 @@
+/// Target chunk length, in characters.
+pub const CHUNK_CHARS: usize = 1000;
+
+/// How much consecutive chunks overlap, in characters.
+pub const CHUNK_OVERLAP: usize = 200;
+
+/// Cap on chunks per file when indexing, so one huge log can't stall it.
+pub const MAX_CHUNKS_PER_FILE: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct Chunk<'a> {
+    pub offset: usize, // char offset into the source text
+    pub text: &'a str,
+}
+
+/// Split text into at most `max_chunks` overlapping chunks of roughly `size`
+/// characters; anything past the last one is left out.
+///
+/// Chunks end on whitespace when possible so words aren't cut in half.
+pub fn split_chunks(text: &str, size: usize, overlap: usize, max_chunks: usize) -> Vec<Chunk<'_>> {
+    let bounds: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    let total = bounds.len() - 1; // number of chars
+
+    let mut chunks = Vec::new();
+    let mut start = 0usize;
+
+    while start < total && chunks.len() < max_chunks {
+        let mut end = (start + size).min(total);
+
+        // Back off to the last whitespace in the second half of the window
+        if end < total {
+            let window = &text[bounds[start + size / 2]..bounds[end]];
+            if let Some(ws) = window.rfind(char::is_whitespace) {
+                let byte_end = bounds[start + size / 2] + ws;
+                end = bounds.partition_point(|b| *b < byte_end);
+            }
+        }
+
+        let piece = text[bounds[start]..bounds[end]].trim();
+        if !piece.is_empty() {
+            chunks.push(Chunk {
+                offset: start,
+                text: piece,
+            });
+        }
+
+        if end >= total {
+            break;
+        }
+
+        start = if end - start > overlap { end - overlap } else { end };
+    }
+
+    chunks
+}
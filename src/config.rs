@@ -0,0 +1,294 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// User settings, persisted as JSON in `~/.config/meow/config.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Directories the indexer walks.
+    pub roots: Vec<PathBuf>,
+    /// Folder names you can use in queries, e.g. `"work": "~/src/company"`.
+    /// A list of directories searches all of them.
+    #[serde(deserialize_with = "one_or_many")]
+    pub aliases: BTreeMap<String, Vec<String>>,
+    /// Which files the indexer and search walkers skip.
+    pub walk: WalkOptions,
+    /// Indexer throughput settings.
+    pub indexing: IndexOptions,
+    /// Which embedding backend to use.
+    pub embedding: EmbeddingConfig,
+    /// Search tuning.
+    pub search: SearchOptions,
+    /// Boosts for recently modified and frequently opened files.
+    pub ranking: RankingOptions,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let roots = match dirs::home_dir() {
+            Some(home) => vec![home.join("Downloads"), home.join("Pictures")],
+            None => Vec::new(),
+        };
+        Self {
+            roots,
+            aliases: BTreeMap::new(),
+            walk: WalkOptions::default(),
+            indexing: IndexOptions::default(),
+            embedding: EmbeddingConfig::default(),
+            search: SearchOptions::default(),
+            ranking: RankingOptions::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WalkOptions {
+    /// Index dotfiles and dot-directories too.
+    pub include_hidden: bool,
+    /// Honor `.gitignore` files (in addition to `.meowignore`).
+    pub respect_gitignore: bool,
+    /// How deep below a root to descend; `None` means no limit.
+    pub max_depth: Option<usize>,
+    /// Don't read or embed the contents of files larger than this many bytes
+    /// (they are still indexed by name and type); `None` means no limit.
+    pub max_file_size: Option<u64>,
+    /// Directory names that are never entered, wherever they appear.
+    pub exclude_dirs: Vec<String>,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            include_hidden: false,
+            respect_gitignore: true,
+            max_depth: None,
+            max_file_size: Some(2 * 1024 * 1024 * 1024),
+            exclude_dirs: [
+                "node_modules", "target", ".git", "__pycache__", ".cache",
+                ".venv", "venv", ".tox", ".gradle", ".idea",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexOptions {
+    /// Files prepared and embedded in parallel.
+    pub concurrency: usize,
+    /// Texts sent to the embedding server per request.
+    pub batch_size: usize,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            batch_size: 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingConfig {
+    /// `ollama`, `openai` (any OpenAI-compatible server) or `hash` (offline).
+    pub provider: String,
+    /// Server base URL; each provider has its own localhost default.
+    pub url: Option<String>,
+    pub model: String,
+    pub api_key: Option<String>,
+    /// Vector size for the `hash` provider.
+    pub dimensions: Option<usize>,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            provider: "ollama".to_string(),
+            url: None,
+            model: "nomic-embed-text".to_string(),
+            api_key: None,
+            dimensions: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Use the approximate (IVF) index when one has been built; `false`
+    /// always scores every stored vector.
+    pub approximate: bool,
+    /// Clusters scanned per query in approximate mode; higher is slower but
+    /// closer to exact.
+    pub nprobe: usize,
+    /// How keyword (BM25) and semantic scores are combined: `weighted` adds
+    /// `lexical_weight` × normalized BM25 to the cosine score, `rrf` uses
+    /// reciprocal-rank fusion.
+    pub fusion: String,
+    pub lexical_weight: f32,
+    /// How a long document's chunk scores fold into its file score: `max`
+    /// (the best passage counts) or `mean` (the document as a whole).
+    pub pooling: String,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            approximate: true,
+            nprobe: 16,
+            fusion: "weighted".to_string(),
+            lexical_weight: 0.3,
+            pooling: "max".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankingOptions {
+    /// Added to the score of a file modified just now; decays with age.
+    pub recency_weight: f32,
+    /// Age at which the recency boost has halved.
+    pub recency_half_life_days: f32,
+    /// Added to the score of a file opened many times via `open <n>`.
+    pub frequency_weight: f32,
+    /// Added to the score of a file opened after similar past queries.
+    pub feedback_weight: f32,
+    /// How close (cosine) a past query must be to count as similar.
+    pub feedback_min_similarity: f32,
+}
+
+impl Default for RankingOptions {
+    fn default() -> Self {
+        Self {
+            recency_weight: 0.05,
+            recency_half_life_days: 30.0,
+            frequency_weight: 0.1,
+            feedback_weight: 0.15,
+            feedback_min_similarity: 0.8,
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("meow")
+            .join("config.json")
+    }
+
+    /// Load the config file, falling back to defaults if it doesn't exist yet.
+    pub fn load() -> anyhow::Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let raw = fs::read_to_string(&path)?;
+        let config = serde_json::from_str(&raw)
+            .map_err(|e| anyhow::anyhow!("Invalid config {}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Directories behind a folder alias (case-insensitive).
+    pub fn alias(&self, name: &str) -> Option<Vec<PathBuf>> {
+        self.aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+            .map(|(_, dirs)| dirs.iter().map(|d| expand_path(d)).collect())
+    }
+
+    /// Configured roots that currently exist on disk.
+    pub fn existing_roots(&self) -> Vec<PathBuf> {
+        self.roots.iter().filter(|r| r.is_dir()).cloned().collect()
+    }
+
+    /// Add a root; returns false if it was already configured.
+    pub fn add_root(&mut self, dir: &str) -> anyhow::Result<bool> {
+        let path = expand_path(dir);
+        if !path.is_dir() {
+            anyhow::bail!("Not a directory: {}", path.display());
+        }
+        let path = path.canonicalize()?;
+
+        if self.roots.contains(&path) {
+            return Ok(false);
+        }
+        self.roots.push(path);
+        Ok(true)
+    }
+
+    /// Remove a root; returns false if it wasn't configured.
+    pub fn remove_root(&mut self, dir: &str) -> bool {
+        let path = expand_path(dir);
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+
+        let before = self.roots.len();
+        self.roots.retain(|r| r != &path && r != &canonical);
+        self.roots.len() != before
+    }
+}
+
+/// Alias targets may be written as a single path or a list of paths.
+fn one_or_many<'de, D: Deserializer<'de>>(d: D) -> Result<BTreeMap<String, Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let raw = BTreeMap::<String, OneOrMany>::deserialize(d)?;
+    Ok(raw
+        .into_iter()
+        .map(|(name, dirs)| match dirs {
+            OneOrMany::One(dir) => (name, vec![dir]),
+            OneOrMany::Many(dirs) => (name, dirs),
+        })
+        .collect())
+}
+
+/// Expand a leading `~` and make relative paths absolute.
+pub fn expand_path(dir: &str) -> PathBuf {
+    let path = match dir.strip_prefix("~") {
+        Some(rest) => {
+            let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+            home.join(rest.trim_start_matches('/'))
+        }
+        None => PathBuf::from(dir),
+    };
+
+    if path.is_absolute() {
+        path
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(&path))
+            .unwrap_or(path)
+    }
+}
+
+/// The short name we show and match against for a root (its last component).
+pub fn root_name(root: &Path) -> String {
+    root.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .to_string()
+}
@@ -1,55 +1,261 @@
+use crate::config::EmbeddingConfig;
 use reqwest::blocking::Client;
+use reqwest::StatusCode;
 use serde_json::json;
+use std::time::Duration;
 
-/// Generate an embedding for a piece of text using Ollama
-pub fn embed_text(text: &str) -> anyhow::Result<Vec<f32>> {
-    let client = Client::new();
+/// Anything that can turn text into vectors.
+///
+/// The indexer shares one embedder between its worker threads, hence `Sync`.
+pub trait Embedder: Send + Sync {
+    /// Embed many texts; returns one vector per input, in order.
+    fn embed(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>>;
 
-    // IMPORTANT: Ollama embeddings expect `prompt`, not `input`
-    let body = json!({
-        "model": "nomic-embed-text",
-        "prompt": text
-    });
+    /// Identifies the model that produced the vectors, e.g. `ollama:nomic-embed-text`.
+    fn model_id(&self) -> String;
 
-    let resp: serde_json::Value = client
-        .post("http://localhost:11434/api/embeddings")
-        .json(&body)
-        .send()?
-        .json()?;
+    fn embed_one(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+        self.embed(&[text.to_string()])?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Embedder returned no vector"))
+    }
+}
+
+/// Build the embedder selected in the config.
+pub fn from_config(cfg: &EmbeddingConfig) -> anyhow::Result<Box<dyn Embedder>> {
+    match cfg.provider.as_str() {
+        "ollama" => Ok(Box::new(OllamaEmbedder::new(
+            cfg.url.as_deref().unwrap_or("http://localhost:11434"),
+            &cfg.model,
+        )?)),
+        "openai" => Ok(Box::new(OpenAiEmbedder::new(
+            cfg.url.as_deref().unwrap_or("http://localhost:8080"),
+            &cfg.model,
+            cfg.api_key.clone(),
+        )?)),
+        "hash" => Ok(Box::new(HashEmbedder::new(cfg.dimensions.unwrap_or(256)))),
+        other => anyhow::bail!("Unknown embedding provider: {other} (expected ollama, openai or hash)"),
+    }
+}
+
+fn http_client() -> anyhow::Result<Client> {
+    Ok(Client::builder().timeout(Duration::from_secs(300)).build()?)
+}
+
+fn parse_vector(v: &serde_json::Value) -> anyhow::Result<Vec<f32>> {
+    let vec: Vec<f32> = v
+        .as_array()
+        .map(|xs| xs.iter().map(|x| x.as_f64().unwrap_or(0.0) as f32).collect())
+        .unwrap_or_default();
+
+    if vec.is_empty() {
+        anyhow::bail!("Received empty embedding");
+    }
+    Ok(vec)
+}
+
+/// Local Ollama server.
+pub struct OllamaEmbedder {
+    client: Client,
+    base_url: String,
+    model: String,
+}
+
+impl OllamaEmbedder {
+    pub fn new(base_url: &str, model: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+        })
+    }
+
+    /// Pre-0.3 Ollama only has `/api/embeddings`, one prompt per call.
+    fn embed_legacy(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+        // IMPORTANT: the legacy endpoint expects `prompt`, not `input`
+        let body = json!({
+            "model": self.model,
+            "prompt": text
+        });
+
+        let resp: serde_json::Value = self
+            .client
+            .post(format!("{}/api/embeddings", self.base_url))
+            .json(&body)
+            .send()?
+            .json()?;
+
+        match resp.get("embedding") {
+            Some(v) => parse_vector(v),
+            None => anyhow::bail!("Invalid embedding response from Ollama: {}", resp),
+        }
+    }
+}
+
+impl Embedder for OllamaEmbedder {
+    fn embed(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = json!({
+            "model": self.model,
+            "input": texts
+        });
+
+        let resp = self
+            .client
+            .post(format!("{}/api/embed", self.base_url))
+            .json(&body)
+            .send()?;
+
+        if resp.status() == StatusCode::NOT_FOUND {
+            return texts.iter().map(|t| self.embed_legacy(t)).collect();
+        }
+
+        let resp: serde_json::Value = resp.json()?;
+
+        // { "embeddings": [[...], [...]] }
+        let Some(arr) = resp.get("embeddings").and_then(|v| v.as_array()) else {
+            anyhow::bail!("Invalid embedding response from Ollama: {}", resp);
+        };
+
+        if arr.len() != texts.len() {
+            anyhow::bail!(
+                "Ollama returned {} embeddings for {} inputs",
+                arr.len(),
+                texts.len()
+            );
+        }
+
+        arr.iter().map(parse_vector).collect()
+    }
+
+    fn model_id(&self) -> String {
+        format!("ollama:{}", self.model)
+    }
+}
+
+/// Any server speaking the OpenAI `/v1/embeddings` API
+/// (llama.cpp, vLLM, LM Studio, ...).
+pub struct OpenAiEmbedder {
+    client: Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiEmbedder {
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key,
+        })
+    }
+}
+
+impl Embedder for OpenAiEmbedder {
+    fn embed(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = json!({
+            "model": self.model,
+            "input": texts
+        });
+
+        let mut req = self
+            .client
+            .post(format!("{}/v1/embeddings", self.base_url))
+            .json(&body);
+        if let Some(key) = &self.api_key {
+            req = req.bearer_auth(key);
+        }
+
+        let resp: serde_json::Value = req.send()?.error_for_status()?.json()?;
+
+        // { "data": [ { "index": 0, "embedding": [...] }, ... ] }
+        let Some(data) = resp.get("data").and_then(|d| d.as_array()) else {
+            anyhow::bail!("Invalid embedding response: {}", resp);
+        };
 
-    // Case 1: { "embedding": [...] }
-    if let Some(arr) = resp.get("embedding").and_then(|v| v.as_array()) {
-        let vec: Vec<f32> = arr
-            .iter()
-            .map(|x| x.as_f64().unwrap_or(0.0) as f32)
-            .collect();
+        let mut out = vec![Vec::new(); texts.len()];
+        for (i, item) in data.iter().enumerate() {
+            let idx = item
+                .get("index")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .unwrap_or(i);
+            let Some(slot) = out.get_mut(idx) else {
+                anyhow::bail!("Embedding index {} out of range", idx);
+            };
+            *slot = parse_vector(item.get("embedding").unwrap_or(&serde_json::Value::Null))?;
+        }
 
-        if vec.is_empty() {
-            anyhow::bail!("Received empty embedding from Ollama");
+        if out.iter().any(|v| v.is_empty()) {
+            anyhow::bail!("Server returned {} embeddings for {} inputs", data.len(), texts.len());
         }
+        Ok(out)
+    }
+
+    fn model_id(&self) -> String {
+        format!("openai:{}", self.model)
+    }
+}
+
+/// Deterministic, offline embedder: hashes words into a fixed number of
+/// buckets. No semantics, but stable across runs, so it's handy for tests
+/// and for machines without a model server.
+pub struct HashEmbedder {
+    dim: usize,
+}
 
-        return Ok(vec);
+impl HashEmbedder {
+    pub fn new(dim: usize) -> Self {
+        Self { dim: dim.max(1) }
     }
 
-    // Case 2: { "data": [ { "embedding": [...] } ] }
-    if let Some(arr) = resp
-        .get("data")
-        .and_then(|d| d.get(0))
-        .and_then(|o| o.get("embedding"))
-        .and_then(|v| v.as_array())
-    {
-        let vec: Vec<f32> = arr
-            .iter()
-            .map(|x| x.as_f64().unwrap_or(0.0) as f32)
-            .collect();
+    fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vec = vec![0.0f32; self.dim];
+
+        let words = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase());
 
-        if vec.is_empty() {
-            anyhow::bail!("Received empty embedding from Ollama");
+        for word in words {
+            let h = fnv1a(word.as_bytes());
+            let bucket = (h % self.dim as u64) as usize;
+            let sign = if (h >> 63) == 0 { 1.0 } else { -1.0 };
+            vec[bucket] += sign;
         }
 
-        return Ok(vec);
+        let norm = vec.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vec.iter_mut().for_each(|x| *x /= norm);
+        }
+        vec
+    }
+}
+
+impl Embedder for HashEmbedder {
+    fn embed(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| self.embed_text(t)).collect())
     }
 
-    // ❌ If neither format matched
-    anyhow::bail!("Invalid embedding response from Ollama: {}", resp);
+    fn model_id(&self) -> String {
+        format!("hash:{}", self.dim)
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
 }
@@ -1,36 +1,95 @@
 use crate::ai::AiAction;
-use crate::embedding::embed_text;
+use crate::ann::IvfIndex;
+use crate::config::{expand_path, root_name, Config, RankingOptions, SearchOptions};
+use crate::embedding;
 use crate::similarity::cosine_similarity;
-use crate::types::SearchResults;
+use crate::types::{ChunkMatch, SearchHit, SearchResults};
 use crate::vector_db::VectorDB;
+use crate::file_ops::{self, Transfer};
+use crate::file_type::{category_for_query, CATEGORIES};
+use crate::path_filter::PathFilter;
+use crate::preview;
+use crate::summarize::summarize_file;
+use crate::trash;
+use crate::time_filter::TimeFilter;
+use crate::walker::walk_files;
 use crate::ai_decider::{decide_best, Candidate};
 
+use std::collections::{HashMap, HashSet};
 
-use chrono::{Duration, Local};
+use chrono::Local;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-pub fn execute_action(action: AiAction) -> anyhow::Result<Option<SearchResults>> {
+/// Progress and notes: stdout in the shell, stderr when stdout carries results.
+macro_rules! say {
+    ($quiet:expr, $($arg:tt)*) => {
+        if $quiet {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// Where and how much to search. The shell uses the defaults; `meow search`
+/// sets them from its flags.
+pub struct SearchScope {
+    pub roots: Vec<PathBuf>, // searched instead of the folder hint, when given
+    pub limit: usize,
+    pub quiet: bool, // stdout is for the caller: notes go to stderr, results aren't printed
+}
+
+impl Default for SearchScope {
+    fn default() -> Self {
+        Self {
+            roots: Vec::new(),
+            limit: 10,
+            quiet: false,
+        }
+    }
+}
+
+/// `explain` prints how the query was interpreted and how every result was scored.
+/// `last` are the results on screen, which "read 2"-style actions refer to.
+pub fn execute_action(
+    action: AiAction,
+    explain: bool,
+    last: Option<&SearchResults>,
+) -> anyhow::Result<Option<SearchResults>> {
     match action.intent.as_str() {
         "search" | "find" => {
-            let res = execute_search(action)?;
+            let res = execute_search(action, explain, &SearchScope::default())?;
             Ok(Some(res))
         }
+        "read" | "show" | "view" => {
+            let (target, results) = resolve_target(action, explain, last)?;
+            if let Some(path) = target {
+                preview::show_file(&path)?;
+            }
+            Ok(results)
+        }
         "open" => {
             println!("(open not implemented yet)");
             Ok(None)
         }
-        "read" => {
-            println!("(read not implemented yet)");
-            Ok(None)
+        "summarize" | "summarise" => {
+            let (target, results) = resolve_target(action, explain, last)?;
+            if let Some(path) = target {
+                let db = VectorDB::new("meow_vectors.db")?;
+                summarize_file(&db, &path)?;
+            }
+            Ok(results)
         }
-        "summarize" => {
-            println!("(summarize not implemented yet)");
-            Ok(None)
+        "move" | "copy" | "rename" | "organize" | "organise" => {
+            execute_transfer(action, explain, last)
         }
-        "delete" => {
-            println!("(delete not implemented yet)");
-            Ok(None)
+        "delete" | "remove" | "trash" => {
+            let (targets, results) = resolve_delete_targets(action, explain, last)?;
+            if !targets.is_empty() {
+                file_ops::trash_files(&targets)?;
+            }
+            Ok(results)
         }
         _ => {
             println!("Unknown intent: {}", action.intent);
@@ -39,15 +98,217 @@ pub fn execute_action(action: AiAction) -> anyhow::Result<Option<SearchResults>>
     }
 }
 
-fn execute_search(action: AiAction) -> anyhow::Result<SearchResults> {
-    println!("Executing AI search...");
+/// The file an action is about: a result number from the last search
+/// ("3", "#3", "result 3"), a path, or else the best match for the query.
+/// Also returns the new results when a search had to be run.
+fn resolve_target(
+    action: AiAction,
+    explain: bool,
+    last: Option<&SearchResults>,
+) -> anyhow::Result<(Option<PathBuf>, Option<SearchResults>)> {
+    let query = action.query.as_deref().unwrap_or("").trim();
+
+    // "2", "#2", "result 2"; "2024 report" is a name to look for
+    if let Some(&[n]) = parse_selection(query).as_deref() {
+        let Some(last) = last.filter(|r| !r.items.is_empty()) else {
+            println!("No previous results. Run a search first.");
+            return Ok((None, None));
+        };
+        return match last.items.get(n.wrapping_sub(1)) {
+            Some(hit) => Ok((Some(PathBuf::from(&hit.path)), None)),
+            None => {
+                println!("Invalid index. Choose 1..{}", last.items.len());
+                Ok((None, None))
+            }
+        };
+    }
+
+    if !query.is_empty() {
+        let path = expand_path(query);
+        if path.is_file() {
+            return Ok((Some(path), None));
+        }
+    }
+
+    let results = execute_search(action, explain, &SearchScope::default())?;
+    let best = results.items.first().map(|hit| PathBuf::from(&hit.path));
+    if let Some(path) = &best {
+        println!("\nUsing the best match: {}", path.display());
+    }
+    Ok((best, Some(results)))
+}
+
+/// Files to delete: result numbers or a path. A description only runs a
+/// search, since what the model thinks matches must not be deleted unseen.
+fn resolve_delete_targets(
+    action: AiAction,
+    explain: bool,
+    last: Option<&SearchResults>,
+) -> anyhow::Result<(Vec<PathBuf>, Option<SearchResults>)> {
+    let query = action.query.as_deref().unwrap_or("").trim();
+
+    if let Some(numbers) = parse_selection(query) {
+        return Ok((pick_results(&numbers, last).unwrap_or_default(), None));
+    }
+
+    if !query.is_empty() {
+        let path = expand_path(query);
+        if trash::is_trashable(&path) {
+            return Ok((vec![path], None));
+        }
+    }
+
+    let results = execute_search(action, explain, &SearchScope::default())?;
+    if !results.items.is_empty() {
+        println!("\nNothing deleted. Check the results, then `delete <n>` the ones to trash.");
+    }
+    Ok((Vec::new(), Some(results)))
+}
+
+/// Move, copy, rename or organize files. Nothing changes before the plan
+/// has been shown and confirmed.
+fn execute_transfer(
+    action: AiAction,
+    explain: bool,
+    last: Option<&SearchResults>,
+) -> anyhow::Result<Option<SearchResults>> {
+    let kind = match action.intent.as_str() {
+        "copy" => Transfer::Copy,
+        "rename" => Transfer::Rename,
+        _ => Transfer::Move,
+    };
+    let Some(destination) = action.destination.clone().filter(|d| !d.trim().is_empty()) else {
+        println!("Where to? Try \"move 2 to Documents/receipts\" or \"rename 1 to notes.txt\".");
+        return Ok(None);
+    };
+    let config = Config::load()?;
+
+    if kind == Transfer::Rename {
+        let (target, results) = resolve_target(action, explain, last)?;
+        if let Some(from) = target {
+            let from = std::path::absolute(&from).unwrap_or(from);
+            let to = renamed_path(&from, destination.trim());
+            if to == from {
+                println!("It already has that name.");
+            } else if to.exists() {
+                println!("{} already exists; not overwriting it.", to.display());
+            } else {
+                file_ops::run_transfer(kind, &[(from, to)])?;
+            }
+        }
+        return Ok(results);
+    }
+
+    let query = action.query.as_deref().unwrap_or("").trim();
+    let (sources, results) = if action.intent.starts_with("organi") {
+        let db = VectorDB::new("meow_vectors.db")?;
+        let (files, filtered) =
+            filtered_files(&action, explain, &SearchScope::default(), &config, &db)?;
+        if !filtered && action.folder_hint.is_none() {
+            println!("Organize needs something to pick files by: a type, a time, a size or a folder.");
+            return Ok(None);
+        }
+        (files, None)
+    } else if let Some(numbers) = parse_selection(query) {
+        (pick_results(&numbers, last).unwrap_or_default(), None)
+    } else {
+        let (target, results) = resolve_target(action, explain, last)?;
+        (target.into_iter().collect(), results)
+    };
+
+    // Absolute, but spelled as indexed: resolving symlinks would miss the rows
+    let dir = resolve_destination(&destination, &config);
+    let dir = std::path::absolute(&dir).unwrap_or(dir);
+    let sources: Vec<PathBuf> = sources
+        .into_iter()
+        .map(|p| std::path::absolute(&p).unwrap_or(p))
+        .filter(|p| p.is_file())
+        .collect();
+    file_ops::run_transfer(kind, &file_ops::plan_into(&sources, &dir))?;
+    Ok(results)
+}
+
+/// Paths of results `numbers` (1-based) from the last search.
+fn pick_results(numbers: &[usize], last: Option<&SearchResults>) -> Option<Vec<PathBuf>> {
+    let Some(last) = last.filter(|r| !r.items.is_empty()) else {
+        println!("No previous results. Run a search first.");
+        return None;
+    };
+    if let Some(bad) = numbers.iter().find(|&&n| n == 0 || n > last.items.len()) {
+        println!("Invalid index {}. Choose 1..{}", bad, last.items.len());
+        return None;
+    }
+    Some(numbers.iter().map(|&n| PathBuf::from(&last.items[n - 1].path)).collect())
+}
+
+/// Where "Documents/receipts", "~/archive" or "work" point. The first part is
+/// resolved like a folder hint; the rest is taken as is and may not exist yet.
+fn resolve_destination(dest: &str, config: &Config) -> PathBuf {
+    let dest = dest.trim().trim_end_matches('/');
+    if dest.starts_with(['/', '~', '.']) {
+        return expand_path(dest);
+    }
+
+    let (first, rest) = dest.split_once('/').unwrap_or((dest, ""));
+    let base = resolve_folder_name(&first.to_lowercase(), config)
+        .and_then(|dirs| dirs.into_iter().next());
+    match base {
+        Some(base) if rest.is_empty() => base,
+        Some(base) => base.join(rest),
+        None => dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(dest),
+    }
+}
+
+/// The new path for a rename. A bare name stays in the same folder and keeps
+/// the old extension if it has none ("budget-2024" → "budget-2024.xlsx").
+fn renamed_path(from: &Path, name: &str) -> PathBuf {
+    let to = if name.contains('/') || name.starts_with('~') {
+        let path = expand_path(name);
+        if path.is_dir() {
+            path.join(from.file_name().unwrap_or_default())
+        } else {
+            path
+        }
+    } else {
+        from.with_file_name(name)
+    };
+
+    match (to.extension(), from.extension()) {
+        (None, Some(ext)) => to.with_extension(ext),
+        _ => to,
+    }
+}
+
+/// Result numbers like "3", "#3", "1 3", "1,3", "2-4" or "results 1 and 2".
+pub fn parse_selection(text: &str) -> Option<Vec<usize>> {
+    let mut numbers = Vec::new();
+    for word in text.split(|c: char| c.is_whitespace() || c == ',') {
+        let word = word.trim_start_matches('#').to_lowercase();
+        if word.is_empty() || matches!(word.as_str(), "and" | "result" | "results" | "number" | "numbers") {
+            continue;
+        }
+        if let Some((a, b)) = word.split_once('-') {
+            let (a, b) = (a.parse::<usize>().ok()?, b.parse::<usize>().ok()?);
+            if a > b || b - a > 100 {
+                return None;
+            }
+            numbers.extend(a..=b);
+        } else {
+            numbers.push(word.parse().ok()?);
+        }
+    }
+    (!numbers.is_empty()).then_some(numbers)
+}
+
+pub fn execute_search(action: AiAction, explain: bool, scope: &SearchScope) -> anyhow::Result<SearchResults> {
+    say!(scope.quiet, "Executing AI search...");
 
     // ---- Guard: query must exist ----
     let raw_query = match &action.query {
         Some(q) if !q.trim().is_empty() => q.as_str(),
         _ => {
-            println!("Cannot search without a query.");
-            return Ok(SearchResults { items: vec![] });
+            say!(scope.quiet, "Cannot search without a query.");
+            return Ok(SearchResults::default());
         }
     };
 
@@ -58,62 +319,209 @@ fn execute_search(action: AiAction) -> anyhow::Result<SearchResults> {
     } else {
         cleaned
     };
+    if explain {
+        let note = if final_query == raw_query { " (unchanged)" } else { "" };
+        say!(scope.quiet, "🔎 query {:?} → cleaned {:?}{}", raw_query, final_query, note);
+    }
 
-    // ---- Folder resolution ----
-    let root = resolve_folder_hint(&action.folder_hint, &action.query);
-    println!("Searching in: {}", root.display());
-
-    // Walk filesystem (for filtering only)
-    let mut files = Vec::new();
-    walk(&root, &mut files);
-
-    if let Some(time) = &action.time_filter {
-        files.retain(|p| file_matches_time(p, time));
+    let config = Config::load()?;
+    let db = VectorDB::new("meow_vectors.db")?;
+    let (files, filtered) = filtered_files(&action, explain, scope, &config, &db)?;
+    // Explicit roots are a hard limit: nothing under them means no results,
+    // not the whole index
+    if files.is_empty() && (filtered || !scope.roots.is_empty()) {
+        let why = if filtered { "No files match the filters." } else { "No files under the given roots." };
+        say!(scope.quiet, "{why}");
+        return Ok(SearchResults {
+            query: final_query,
+            ..Default::default()
+        });
     }
 
     // ---- Load embeddings ----
-    let db = VectorDB::new("meow_vectors.db")?;
 
-    println!("Generating query embedding...");
-    let query_vec = embed_text(&final_query)?;
+    say!(scope.quiet, "Generating query embedding...");
+    let embedder = embedding::from_config(&config.embedding)?;
+    let query_vec = embedder.embed_one(&final_query)?;
 
-    println!("Loading file embeddings...");
-    let mut vectors = db.load_all()?;
-    println!("Loaded {} vectors from DB", vectors.len());
+    // Vectors from another model live in a different space; comparing them is noise
+    if let Some(meta) = db.load_meta()?
+        && (meta.model != embedder.model_id() || meta.dimension != query_vec.len())
+    {
+        anyhow::bail!(
+            "Index was built with {} ({} dims) but the configured model is {} ({} dims). \
+             Run `meow index --rebuild`.",
+            meta.model,
+            meta.dimension,
+            embedder.model_id(),
+            query_vec.len()
+        );
+    }
+
+    // Approximate mode only loads the clusters nearest to the query
+    let ivf = if config.search.approximate {
+        IvfIndex::load(&db)?
+    } else {
+        None
+    };
+    let clusters = ivf
+        .as_ref()
+        .map(|index| index.probe(&query_vec, config.search.nprobe));
+    if let (Some(index), Some(probed)) = (&ivf, &clusters) {
+        say!(scope.quiet, "Approximate search: scanning {} of {} clusters", probed.len(), index.len());
+    }
+
+    say!(scope.quiet, "Loading file embeddings...");
+    let mut vectors = db.load_all(clusters.as_deref())?;
+    let mut chunks = db.load_chunks(clusters.as_deref())?;
+    say!(
+        scope.quiet,
+        "Loaded {} vectors and {} chunks from DB",
+        vectors.len(),
+        chunks.len()
+    );
 
     // Restrict vectors to files under selected root
-    if !files.is_empty() {
-        use std::collections::HashSet;
-        let allowed: HashSet<String> = files
-            .iter()
-            .map(|p| p.to_string_lossy().to_string())
-            .collect();
+    let allowed: Option<HashSet<String>> = if files.is_empty() {
+        None
+    } else {
+        Some(files.iter().map(|p| p.to_string_lossy().to_string()).collect())
+    };
+    if let Some(allowed) = &allowed {
         vectors.retain(|(path, _)| allowed.contains(path));
+        chunks.retain(|c| allowed.contains(&c.path));
+    }
+
+    // Guard against stray rows of another dimension (e.g. a half-finished rebuild)
+    let before = vectors.len();
+    vectors.retain(|(_, v)| v.len() == query_vec.len());
+    chunks.retain(|c| c.vector.len() == query_vec.len());
+    if vectors.len() < before {
+        say!(
+            scope.quiet,
+            "⚠ Ignored {} vectors with the wrong dimension; consider `meow index --rebuild`.",
+            before - vectors.len()
+        );
     }
 
-    // ---- Score (semantic only; hybrid already handled elsewhere if needed) ----
-    let mut scored: Vec<(String, f32)> = vectors
+    // ---- Score chunks, grouped back to their file ----
+    let mut chunk_scores: HashMap<String, Vec<f32>> = HashMap::new();
+    let mut best_chunks: HashMap<String, ChunkMatch> = HashMap::new();
+
+    for chunk in chunks {
+        let s = cosine_similarity(&query_vec, &chunk.vector);
+        let s = if s.is_nan() { 0.0 } else { s };
+
+        chunk_scores.entry(chunk.path.clone()).or_default().push(s);
+
+        let better = best_chunks
+            .get(&chunk.path)
+            .is_none_or(|best| s > best.score);
+        if better {
+            best_chunks.insert(
+                chunk.path,
+                ChunkMatch {
+                    offset: chunk.offset,
+                    score: s,
+                    preview: chunk.preview,
+                },
+            );
+        }
+    }
+
+    // ---- Semantic score per file ----
+    let pooling = Pooling::from_config(&config.search);
+    // Whole-file cosine before chunk pooling, kept only for `explain`
+    let mut file_cosine: HashMap<String, f32> = HashMap::new();
+    let mut scored: Vec<SearchHit> = vectors
         .into_iter()
         .map(|(path, vec)| {
             let s = cosine_similarity(&query_vec, &vec);
-            (path, if s.is_nan() { 0.0 } else { s })
+            let file_score = if s.is_nan() { 0.0 } else { s };
+            if explain {
+                file_cosine.insert(path.clone(), file_score);
+            }
+
+            let semantic = match chunk_scores.get(&path) {
+                Some(cs) => pooling.pool(file_score, cs),
+                None => file_score,
+            };
+
+            SearchHit {
+                chunk: best_chunks.remove(&path),
+                path,
+                score: semantic,
+                semantic,
+                lexical: 0.0,
+                recency: 0.0,
+                opens: 0,
+                learned: 0.0,
+            }
         })
         .collect();
 
+    // In approximate mode a chunk can sit in a probed cluster while its file
+    // vector doesn't; let the best chunk stand in for the file.
+    for (path, chunk) in best_chunks {
+        scored.push(SearchHit {
+            score: chunk.score,
+            semantic: chunk.score,
+            lexical: 0.0,
+            recency: 0.0,
+            opens: 0,
+            learned: 0.0,
+            chunk: Some(chunk),
+            path,
+        });
+    }
+
+    // ---- Keyword (BM25) score, fused with the semantic one ----
+    let terms: Vec<String> = final_query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect();
+    let mut lexical: HashMap<String, f32> = db.search_text(&terms, 200)?.into_iter().collect();
+    if let Some(allowed) = &allowed {
+        lexical.retain(|path, _| allowed.contains(path));
+    }
+    fuse_scores(&mut scored, lexical, &config.search);
+    let learned = learned_preferences(&db, &embedder.model_id(), &query_vec, &config.ranking)?;
+    let boost_scale = boost_scale(&scored, &config.search);
+    apply_boosts(&mut scored, &db, &config.ranking, &learned, boost_scale)?;
+
     if scored.is_empty() {
-        println!("No matches found.");
-        return Ok(SearchResults { items: vec![] });
+        say!(scope.quiet, "No matches found.");
+        return Ok(SearchResults {
+            query: final_query,
+            model: embedder.model_id(),
+            ..Default::default()
+        });
     }
 
-    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.sort_by(|a, b| b.score.total_cmp(&a.score));
 
-    // ---- Build candidates (max 10) ----
-    let top = scored.into_iter().take(10).collect::<Vec<_>>();
+    // ---- Build candidates (max `limit`), skipping files deleted since indexing ----
+    let top = scored
+        .into_iter()
+        .filter(|hit| Path::new(&hit.path).exists())
+        .take(scope.limit)
+        .collect::<Vec<_>>();
+
+    if top.is_empty() {
+        say!(scope.quiet, "No matches found.");
+        return Ok(SearchResults {
+            query: final_query,
+            model: embedder.model_id(),
+            ..Default::default()
+        });
+    }
 
     let candidates: Vec<Candidate> = top
         .iter()
         .enumerate()
-        .map(|(i, (path, score))| {
+        .map(|(i, hit)| {
+            let path = &hit.path;
             let p = std::path::Path::new(path);
 
             Candidate {
@@ -132,7 +540,7 @@ fn execute_search(action: AiAction) -> anyhow::Result<SearchResults> {
                     .and_then(|n| n.to_str())
                     .unwrap_or("")
                     .to_string(),
-                score: *score,
+                score: hit.score,
             }
         })
         .collect();
@@ -147,34 +555,431 @@ fn execute_search(action: AiAction) -> anyhow::Result<SearchResults> {
         let ambiguous = best < 0.75 && (best - second) < 0.08;
 
         if ambiguous {
-            sure_pick = decide_best(raw_query, &candidates).ok().flatten();
+            let decision = decide_best(raw_query, &candidates);
+            if explain {
+                let answer = match &decision {
+                    Ok(Some(idx)) => format!("picked [{}]", idx),
+                    Ok(None) => "no confident pick".to_string(),
+                    Err(e) => format!("failed: {e}"),
+                };
+                say!(
+                    scope.quiet,
+                    "🔎 AI decider consulted (top two {:.4} / {:.4}): {}",
+                    best,
+                    second,
+                    answer
+                );
+            }
+            sure_pick = decision.ok().flatten();
+        } else if explain {
+            say!(
+                scope.quiet,
+                "🔎 AI decider not consulted (top two {:.4} / {:.4} are not ambiguous)",
+                best,
+                second
+            );
         }
+    } else if explain {
+        say!(scope.quiet, "🔎 AI decider not consulted (single candidate)");
     }
 
+    // `meow search --json --explain` still explains, on stderr
+    let show = !scope.quiet || explain;
+    let print_details = |hit: &SearchHit| {
+        if explain {
+            let chunk_count = chunk_scores.get(&hit.path).map_or(0, |c| c.len());
+            explain_hit(
+                hit,
+                file_cosine.get(&hit.path).copied(),
+                chunk_count,
+                pooling,
+                boost_scale,
+                &config,
+                scope.quiet,
+            );
+        } else {
+            print_breakdown(hit, &config.ranking);
+        }
+        print_chunk(hit, scope.quiet);
+    };
+
     // ---- Output & result ordering ----
     let mut items = Vec::new();
 
     if let Some(idx) = sure_pick {
-        let picked = &candidates[idx - 1];
-        println!("\n🎯 Best match (AI confirmed):");
-        println!("★ {:.4} → {}", picked.score, picked.path);
+        let picked = &top[idx - 1];
+        if show {
+            say!(scope.quiet, "\n🎯 Best match (AI confirmed):");
+            say!(scope.quiet, "★ {:.4} → {}", picked.score, picked.path);
+            print_details(picked);
+        }
 
         // Ensure sure-shot is first (open 1)
-        items.push(picked.path.clone());
+        items.push(picked.clone());
     }
 
-    println!("\n😼 Results:");
-    for c in &candidates {
+    if show {
+        say!(scope.quiet, "\n😼 Results:");
+    }
+    for (c, hit) in candidates.iter().zip(&top) {
         // Skip duplicate if already added as sure-shot
         if sure_pick == Some(c.idx) {
             continue;
         }
 
-        println!("[{}] {:.4} → {}", c.idx, c.score, c.path);
-        items.push(c.path.clone());
+        // Numbered as `open <n>` will see them, after the sure-shot
+        if show {
+            say!(scope.quiet, "[{}] {:.4} → {}", items.len() + 1, c.score, c.path);
+            print_details(hit);
+        }
+        items.push(hit.clone());
+    }
+
+    Ok(SearchResults {
+        query: final_query,
+        query_vector: query_vec,
+        model: embedder.model_id(),
+        items,
+    })
+}
+
+/// Files under the roots the action points at that pass its time, size,
+/// extension, glob and type filters, and whether any filter applied.
+fn filtered_files(
+    action: &AiAction,
+    explain: bool,
+    scope: &SearchScope,
+    config: &Config,
+    db: &VectorDB,
+) -> anyhow::Result<(Vec<PathBuf>, bool)> {
+    // ---- Folder resolution ----
+    // Indexed paths are absolute, so the walked ones must be too. Symlinks
+    // stay as they are: the indexer keeps roots as configured.
+    let mut roots: Vec<PathBuf> = Vec::new();
+    let chosen = if scope.roots.is_empty() {
+        resolve_folder_hint(&action.folder_hint, &action.query, config)
+    } else {
+        scope.roots.clone()
+    };
+    for root in chosen {
+        let root = std::path::absolute(&root).unwrap_or(root);
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    // A root inside another would be walked twice
+    let all = roots.clone();
+    roots.retain(|r| !all.iter().any(|other| other != r && r.starts_with(other)));
+
+    let shown: Vec<String> = roots.iter().map(|r| r.display().to_string()).collect();
+    say!(scope.quiet, "Searching in: {}", shown.join(", "));
+    if explain {
+        say!(scope.quiet, "🔎 folder hint {:?} → {}", action.folder_hint, shown.join(", "));
+    }
+
+    // Walk filesystem (for filtering only)
+    let mut files: Vec<PathBuf> = roots.iter().flat_map(|r| walk_files(r, &config.walk)).collect();
+
+    let walked = files.len();
+    let time_filter = action.time_filter.as_deref().filter(|t| !t.trim().is_empty());
+    let parsed_time = time_filter.map(|t| (t, TimeFilter::parse(t, Local::now())));
+    match &parsed_time {
+        Some((_, Some(filter))) => files.retain(|p| filter.matches(p)),
+        Some((text, None)) => say!(scope.quiet, "⚠ Didn't understand the time filter {:?}; ignoring it.", text),
+        None => {}
+    }
+    if explain {
+        match &parsed_time {
+            None => say!(scope.quiet, "🔎 no time filter ({} files under the roots)", walked),
+            Some((text, Some(filter))) => say!(
+                scope.quiet,
+                "🔎 time filter {:?} → {}: kept {} of {} files",
+                text,
+                filter,
+                files.len(),
+                walked
+            ),
+            Some((text, None)) => say!(scope.quiet, "🔎 time filter {:?} not understood, nothing excluded", text),
+        }
+    }
+
+    // ---- Size, extension and glob filters ----
+    let path_filter = match PathFilter::from_action(action) {
+        Ok((filter, warnings)) => {
+            for warning in warnings {
+                say!(scope.quiet, "⚠ {warning}");
+            }
+            filter
+        }
+        Err(e) => {
+            say!(scope.quiet, "⚠ {e}; ignoring the size / extension / glob filters.");
+            None
+        }
+    };
+    if let Some(filter) = &path_filter {
+        let before = files.len();
+        files.retain(|p| {
+            let root = roots.iter().find(|r| p.starts_with(r)).unwrap_or(&roots[0]);
+            filter.matches(p, root)
+        });
+        if explain {
+            say!(scope.quiet, "🔎 {}: kept {} of {} files", filter, files.len(), before);
+        }
+    }
+
+    // ---- File type, from categories recorded at index time ----
+    let file_type = action.file_type.as_deref().filter(|t| !t.trim().is_empty());
+    let category = file_type.and_then(category_for_query);
+    if let Some(category) = category {
+        let categories = db.load_categories()?;
+        let before = files.len();
+        files.retain(|p| {
+            categories
+                .get(p.to_string_lossy().as_ref())
+                .is_some_and(|c| c.as_deref() == Some(category))
+        });
+        if explain {
+            say!(
+                scope.quiet,
+                "🔎 file type {:?} → {}: kept {} of {} files",
+                file_type.unwrap_or_default(),
+                category,
+                files.len(),
+                before
+            );
+        }
+    } else if let Some(ft) = file_type
+        && !matches!(ft.trim().to_lowercase().as_str(), "any" | "all" | "file" | "files")
+    {
+        say!(
+            scope.quiet,
+            "⚠ Unknown file type {:?} (try one of: {}); ignoring it.",
+            ft,
+            CATEGORIES.join(", ")
+        );
+    }
+
+    // A filter that leaves nothing means no results, not "search everything"
+    let filtered =
+        matches!(parsed_time, Some((_, Some(_)))) || path_filter.is_some() || category.is_some();
+    Ok((files, filtered))
+}
+
+/// Show what the score is made of, when it isn't just cosine similarity.
+fn print_breakdown(hit: &SearchHit, ranking: &RankingOptions) {
+    let mut parts = vec![format!("semantic {:.3}", hit.semantic)];
+    if hit.lexical > 0.0 {
+        parts.push(format!("keyword {:.3}", hit.lexical));
+    }
+    if ranking.recency_weight > 0.0 && hit.recency >= 0.001 {
+        parts.push(format!("recency {:.3}", hit.recency));
+    }
+    if hit.opens > 0 {
+        parts.push(format!("opened {}×", hit.opens));
+    }
+    if hit.learned > 0.0 {
+        parts.push(format!("learned {:.3}", hit.learned));
+    }
+
+    if parts.len() > 1 {
+        println!("      ({})", parts.join(" · "));
+    }
+}
+
+/// Every component of a hit's score, for `explain` mode.
+fn explain_hit(
+    hit: &SearchHit,
+    cosine: Option<f32>,
+    chunk_count: usize,
+    pooling: Pooling,
+    boost_scale: f32,
+    config: &Config,
+    quiet: bool,
+) {
+    let cosine = match cosine {
+        Some(c) => format!("{:.4}", c),
+        None => "n/a (file vector not scanned)".to_string(),
+    };
+    say!(
+        quiet,
+        "      cosine {} · {} chunks, {:?} pooled → semantic {:.4}",
+        cosine, chunk_count, pooling, hit.semantic
+    );
+
+    let ranking = &config.ranking;
+    let frequency = hit.opens as f32 / (hit.opens as f32 + 2.0);
+    let recency_boost = boost_scale * ranking.recency_weight * hit.recency;
+    let frequency_boost = boost_scale * ranking.frequency_weight * frequency;
+    let learned_boost = boost_scale * ranking.feedback_weight * hit.learned;
+    let fused = hit.score - recency_boost - frequency_boost - learned_boost;
+
+    let keyword = if config.search.fusion == "rrf" {
+        format!("keyword {:.3} (rrf fused → {:.4})", hit.lexical, fused)
+    } else {
+        let weight = config.search.lexical_weight;
+        format!("keyword {:.3} × {} = +{:.4}", hit.lexical, weight, weight * hit.lexical)
+    };
+    say!(
+        quiet,
+        "      {} · recency {:.3} → +{:.4} · opened {}× → +{:.4} · learned {:.3} → +{:.4} · total {:.4}",
+        keyword, hit.recency, recency_boost, hit.opens, frequency_boost, hit.learned, learned_boost, hit.score
+    );
+}
+
+fn print_chunk(hit: &SearchHit, quiet: bool) {
+    if let Some(chunk) = &hit.chunk {
+        say!(
+            quiet,
+            "      ↳ chunk @{} ({:.4}): \"{}…\"",
+            chunk.offset, chunk.score, chunk.preview
+        );
+    }
+}
+
+/// Merge BM25 scores into the semantic hits (adding keyword-only hits) and
+/// set each hit's final `score`.
+fn fuse_scores(hits: &mut Vec<SearchHit>, mut lexical: HashMap<String, f32>, opts: &SearchOptions) {
+    let max_lex = lexical.values().copied().fold(0.0f32, f32::max);
+
+    for hit in hits.iter_mut() {
+        if let Some(bm25) = lexical.remove(&hit.path) {
+            hit.lexical = bm25 / max_lex;
+        }
+    }
+    // Keyword matches the semantic side didn't surface
+    for (path, bm25) in lexical {
+        hits.push(SearchHit {
+            path,
+            score: 0.0,
+            semantic: 0.0,
+            lexical: bm25 / max_lex,
+            recency: 0.0,
+            opens: 0,
+            learned: 0.0,
+            chunk: None,
+        });
+    }
+
+    if opts.fusion == "rrf" {
+        // Reciprocal-rank fusion: 1/(k + rank) summed over both rankings
+        const K: f32 = 60.0;
+        let mut by_semantic: Vec<usize> = (0..hits.len()).collect();
+        by_semantic.sort_by(|a, b| hits[*b].semantic.total_cmp(&hits[*a].semantic));
+        let mut by_lexical: Vec<usize> = (0..hits.len()).filter(|i| hits[*i].lexical > 0.0).collect();
+        by_lexical.sort_by(|a, b| hits[*b].lexical.total_cmp(&hits[*a].lexical));
+
+        for hit in hits.iter_mut() {
+            hit.score = 0.0;
+        }
+        for (rank, i) in by_semantic.into_iter().enumerate() {
+            hits[i].score += 1.0 / (K + rank as f32 + 1.0);
+        }
+        for (rank, i) in by_lexical.into_iter().enumerate() {
+            hits[i].score += 1.0 / (K + rank as f32 + 1.0);
+        }
+    } else {
+        for hit in hits.iter_mut() {
+            hit.score = hit.semantic + opts.lexical_weight * hit.lexical;
+        }
+    }
+}
+
+/// What the ranking weights are relative to. Weighted scores sit around
+/// cosine similarity (0..1), but RRF scores are only ~0.016–0.033, where the
+/// raw weights would drown the fusion; there they scale with the top score.
+fn boost_scale(hits: &[SearchHit], opts: &SearchOptions) -> f32 {
+    if opts.fusion == "rrf" {
+        hits.iter().map(|h| h.score).fold(0.0, f32::max)
+    } else {
+        1.0
+    }
+}
+
+/// Add recency (decaying with file age), open-frequency and learned-preference
+/// boosts, each weight multiplied by `scale`.
+fn apply_boosts(
+    hits: &mut [SearchHit],
+    db: &VectorDB,
+    opts: &RankingOptions,
+    learned: &HashMap<String, f32>,
+    scale: f32,
+) -> anyhow::Result<()> {
+    let records = db.load_file_records()?;
+    let opens = db.load_open_counts()?;
+    let now = Local::now().timestamp();
+    let half_life = opts.recency_half_life_days.max(0.01);
+
+    for hit in hits.iter_mut() {
+        if let Some(record) = records.get(&hit.path) {
+            let age_days = (now - record.state.modified).max(0) as f32 / 86_400.0;
+            hit.recency = 0.5f32.powf(age_days / half_life);
+        }
+        hit.opens = opens.get(&hit.path).copied().unwrap_or(0);
+        hit.learned = learned.get(&hit.path).copied().unwrap_or(0.0);
+
+        // Saturates: 1 open → 0.33, 4 → 0.67, 10 → 0.83
+        let frequency = hit.opens as f32 / (hit.opens as f32 + 2.0);
+        hit.score += scale
+            * (opts.recency_weight * hit.recency
+                + opts.frequency_weight * frequency
+                + opts.feedback_weight * hit.learned);
+    }
+
+    Ok(())
+}
+
+/// Files the user opened after queries similar to this one, scored 0..1.
+///
+/// Each past choice counts by how far its query's similarity clears
+/// `feedback_min_similarity`; the sum saturates so a handful of choices
+/// is enough for the full boost.
+fn learned_preferences(
+    db: &VectorDB,
+    model: &str,
+    query_vec: &[f32],
+    opts: &RankingOptions,
+) -> anyhow::Result<HashMap<String, f32>> {
+    let min_sim = opts.feedback_min_similarity.clamp(0.0, 0.99);
+    let mut strength: HashMap<String, f32> = HashMap::new();
+
+    for row in db.load_feedback(model)? {
+        let sim = cosine_similarity(query_vec, &row.query_vector);
+        if sim >= min_sim {
+            *strength.entry(row.path).or_default() += (sim - min_sim) / (1.0 - min_sim);
+        }
+    }
+
+    Ok(strength
+        .into_iter()
+        .map(|(path, s)| (path, s / (s + 0.5)))
+        .collect())
+}
+
+/// How per-chunk scores are folded back into a single file score.
+#[derive(Debug, Clone, Copy)]
+enum Pooling {
+    Max,
+    Mean,
+}
+
+impl Pooling {
+    /// `search.pooling`; anything but `mean` is the default max pooling.
+    fn from_config(opts: &SearchOptions) -> Self {
+        match opts.pooling.as_str() {
+            "mean" => Pooling::Mean,
+            _ => Pooling::Max,
+        }
     }
 
-    Ok(SearchResults { items })
+    fn pool(self, file_score: f32, chunk_scores: &[f32]) -> f32 {
+        match self {
+            Pooling::Max => chunk_scores.iter().copied().fold(file_score, f32::max),
+            Pooling::Mean => {
+                let sum: f32 = file_score + chunk_scores.iter().sum::<f32>();
+                sum / (chunk_scores.len() + 1) as f32
+            }
+        }
+    }
 }
 
 
@@ -191,61 +996,163 @@ fn clean_query(q: &str) -> String {
         .join(" ")
 }
 
-fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_file() {
-                out.push(path);
-            } else if path.is_dir() {
-                walk(&path, out);
+/// Directories to search: from the LLM's folder hint (which may name several,
+/// "work and downloads"), else folders named in the query, else `.`.
+fn resolve_folder_hint(hint: &Option<String>, query: &Option<String>, config: &Config) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+
+    if let Some(h) = hint {
+        for name in split_hint(h) {
+            match resolve_folder_name(&name, config) {
+                Some(dirs) => roots.extend(dirs),
+                None => println!("⚠ No folder matches {:?}", name),
+            }
+        }
+    }
+
+    // No usable hint: look for "in <folder>" in the query itself (exact names
+    // only). A bare word isn't enough: "my tax documents" isn't about ~/Documents.
+    if roots.is_empty()
+        && let Some(q) = query
+    {
+        let lower = q.to_lowercase();
+        let words: Vec<&str> = lower
+            .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+            .filter(|w| !w.is_empty())
+            .collect();
+        for (i, word) in words.iter().enumerate() {
+            if !matches!(*word, "in" | "inside" | "under" | "within" | "on") {
+                continue;
             }
+            let rest = &words[i + 1..];
+            let Some(name) = rest.iter().find(|w| !matches!(**w, "my" | "the")) else {
+                continue;
+            };
+            let found = config
+                .alias(name)
+                .or_else(|| user_dir(name, true).map(|d| vec![d]))
+                .or_else(|| root_named(name, config).map(|d| vec![d]));
+            roots.extend(found.into_iter().flatten());
         }
     }
+
+    if roots.is_empty() {
+        roots.push(PathBuf::from("."));
+    }
+    roots
 }
 
-fn resolve_folder_hint(hint: &Option<String>, query: &Option<String>) -> PathBuf {
-    let home = dirs::home_dir().unwrap_or(PathBuf::from("."));
+/// "work, downloads", "work and downloads" → ["work", "downloads"].
+fn split_hint(hint: &str) -> Vec<String> {
+    hint.to_lowercase()
+        .replace([',', '&', '+', ';'], " and ")
+        .split(" and ")
+        .flat_map(|part| part.split(" or "))
+        .map(|part| {
+            part.split_whitespace()
+                .filter(|w| !matches!(*w, "my" | "the" | "folder" | "folders" | "directory" | "dir"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|part| !part.is_empty())
+        .collect()
+}
 
-    if let Some(h) = hint {
-        return match h.to_lowercase().as_str() {
-            "downloads" => home.join("Downloads"),
-            "pictures" => home.join("Pictures"),
-            _ => PathBuf::from("."),
-        };
+/// One folder name: a path, an alias, an XDG user dir, a root name, or the
+/// closest-named directory among those and the folders in home and the roots.
+fn resolve_folder_name(name: &str, config: &Config) -> Option<Vec<PathBuf>> {
+    if name.starts_with(['/', '~', '.']) {
+        let path = expand_path(name);
+        return path.is_dir().then(|| vec![path]);
+    }
+    if let Some(dirs) = config.alias(name) {
+        return Some(dirs);
+    }
+    if let Some(dir) = user_dir(name, false).or_else(|| root_named(name, config)) {
+        return Some(vec![dir]);
     }
 
-    // simple inference
-    if let Some(q) = query {
-        let q = q.to_lowercase();
-        if q.contains("download") {
-            return home.join("Downloads");
-        }
-        if q.contains("photo") || q.contains("picture") || q.contains("image") {
-            return home.join("Pictures");
+    // Fuzzy: typos ("donwloads") and partial names ("company" → company-repo)
+    let mut candidates: Vec<(String, Vec<PathBuf>)> = config
+        .aliases
+        .keys()
+        .filter_map(|alias| Some((alias.to_lowercase(), config.alias(alias)?)))
+        .collect();
+    let mut parents: Vec<PathBuf> = config.existing_roots();
+    parents.extend(dirs::home_dir());
+    for parent in &parents {
+        candidates.push((root_name(parent).to_lowercase(), vec![parent.clone()]));
+        let Ok(entries) = fs::read_dir(parent) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let dir_name = entry.file_name().to_string_lossy().to_lowercase();
+            if entry.path().is_dir() && !dir_name.starts_with('.') {
+                candidates.push((dir_name, vec![entry.path()]));
+            }
         }
     }
 
-    PathBuf::from(".")
+    candidates
+        .into_iter()
+        .map(|(candidate, dirs)| (folder_similarity(name, &candidate), dirs))
+        .filter(|(score, _)| *score >= 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, dirs)| dirs)
 }
 
-fn file_matches_time(path: &PathBuf, filter: &str) -> bool {
-    if let Ok(meta) = fs::metadata(path) {
-        if let Ok(modified) = meta.modified() {
-            let modified_dt = chrono::DateTime::<Local>::from(modified);
-            let now = Local::now();
+fn folder_similarity(name: &str, candidate: &str) -> f64 {
+    let edit = strsim::normalized_damerau_levenshtein(name, candidate);
+    // A distinctive prefix counts almost as much as a near-exact name
+    let prefix = if name.len() >= 3 && candidate.starts_with(name) { 0.85 } else { 0.0 };
+    edit.max(prefix)
+}
 
-            match filter {
-                "yesterday" => {
-                    let y = now - Duration::days(1);
-                    return modified_dt.date_naive() == y.date_naive();
-                }
-                "today" => {
-                    return modified_dt.date_naive() == now.date_naive();
-                }
-                _ => return true,
-            }
-        }
+/// XDG user directories by common name. `from_query` skips singular
+/// words ("document", "video") that usually describe the file, not the folder.
+fn user_dir(name: &str, from_query: bool) -> Option<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    let (dir, fallback) = match name {
+        "downloads" | "download" | "downloaded" => (dirs::download_dir(), "Downloads"),
+        "pictures" | "picture" | "photos" | "photo" | "images" | "image" => (dirs::picture_dir(), "Pictures"),
+        "documents" => (dirs::document_dir(), "Documents"),
+        "desktop" => (dirs::desktop_dir(), "Desktop"),
+        "music" => (dirs::audio_dir(), "Music"),
+        "videos" | "movies" => (dirs::video_dir(), "Videos"),
+        "document" | "docs" if !from_query => (dirs::document_dir(), "Documents"),
+        "video" | "movie" if !from_query => (dirs::video_dir(), "Videos"),
+        "songs" | "audio" if !from_query => (dirs::audio_dir(), "Music"),
+        "home" if !from_query => (dirs::home_dir(), ""),
+        _ => return None,
+    };
+    Some(dir.unwrap_or_else(|| home.join(fallback)))
+}
+
+fn root_named(name: &str, config: &Config) -> Option<PathBuf> {
+    config
+        .roots
+        .iter()
+        .find(|r| !name.is_empty() && root_name(r).to_lowercase() == name)
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rename_keeps_the_extension() {
+        let from = Path::new("/docs/budget.xlsx");
+        assert_eq!(renamed_path(from, "budget-2024"), Path::new("/docs/budget-2024.xlsx"));
+        assert_eq!(renamed_path(from, "budget-2024.csv"), Path::new("/docs/budget-2024.csv"));
+        assert_eq!(renamed_path(from, "/archive/old"), Path::new("/archive/old.xlsx"));
+        assert_eq!(renamed_path(Path::new("/docs/README"), "notes"), Path::new("/docs/notes"));
+    }
+
+    #[test]
+    fn rename_into_a_folder_keeps_the_name() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let name = format!("{}/", tmp.path().display());
+        assert_eq!(renamed_path(Path::new("/docs/budget.xlsx"), &name), tmp.path().join("budget.xlsx"));
     }
-    false
 }
@@ -0,0 +1,236 @@
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+use pdf_extract::{output_doc_page, Document, PlainTextOutput};
+use std::fs::{self, File};
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+/// Never read more than this many bytes from a single file.
+const MAX_READ_BYTES: u64 = 256 * 1024;
+
+/// PDFs are parsed in full, so skip anything bigger than this.
+const MAX_PDF_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Only the first pages of a PDF are extracted.
+pub const MAX_PDF_PAGES: usize = 50;
+
+/// Upper bound on the extracted text kept for chunking.
+const MAX_EXTRACT_CHARS: usize = 64_000;
+
+/// Upper bound on the text that goes into the whole-file embedding.
+pub const MAX_TEXT_CHARS: usize = 4000;
+
+/// Summaries read much further into a file than indexing does (about 90
+/// model calls' worth of text at most).
+const SUMMARY_READ_BYTES: u64 = 4 * 1024 * 1024;
+const SUMMARY_PDF_PAGES: usize = 500;
+const SUMMARY_CHARS: usize = 500_000;
+
+/// Extensions whose body we can read as plain text.
+const TEXT_EXTS: &[&str] = &[
+    // documents
+    "txt", "md", "markdown", "rst", "org", "log",
+    // data
+    "csv", "tsv", "json", "jsonl", "yaml", "yml", "toml", "ini", "xml",
+    // source code
+    "rs", "py", "js", "ts", "jsx", "tsx", "go", "java", "kt", "c", "h", "cpp", "hpp", "cs",
+    "rb", "php", "swift", "sh", "bash", "zsh", "sql", "html", "css", "scss", "lua",
+];
+
+pub fn is_text_ext(ext: &str) -> bool {
+    TEXT_EXTS.contains(&ext)
+}
+
+/// Extract the readable body of a file, if we know how to.
+///
+/// Returns `Ok(None)` for file types we don't extract from (images, binaries, ...).
+pub fn extract_text(path: &Path) -> anyhow::Result<Option<String>> {
+    let text = extract(path, MAX_READ_BYTES, MAX_PDF_PAGES, MAX_EXTRACT_CHARS)?;
+    Ok(text.map(|(text, _)| text))
+}
+
+/// A file's text for summarizing, with the much larger `SUMMARY_*` limits.
+/// The flag is set when even those cut the file short.
+pub fn extract_for_summary(path: &Path) -> anyhow::Result<Option<(String, bool)>> {
+    extract(path, SUMMARY_READ_BYTES, SUMMARY_PDF_PAGES, SUMMARY_CHARS)
+}
+
+/// The text of `path` within the given limits, and whether any of them hit.
+fn extract(
+    path: &Path,
+    max_bytes: u64,
+    max_pages: usize,
+    max_chars: usize,
+) -> anyhow::Result<Option<(String, bool)>> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let (text, cut) = if ext == "pdf" {
+        let (pages, total) = pdf_pages(path, max_pages)?;
+        let text = pages
+            .into_iter()
+            .map(|(page, body)| format!("[page {}] {}", page, normalize_whitespace(&body)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        (text, total > max_pages)
+    } else if is_text_ext(&ext) {
+        let cut = fs::metadata(path)?.len() > max_bytes;
+        (normalize_whitespace(&read_text_prefix(path, max_bytes)?), cut)
+    } else {
+        return Ok(None);
+    };
+
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    let cut = cut || text.chars().nth(max_chars).is_some();
+    Ok(Some((truncate_chars(&text, max_chars), cut)))
+}
+
+/// Read up to `MAX_READ_BYTES` and decode it with a detected encoding.
+pub fn read_text_file(path: &Path) -> anyhow::Result<String> {
+    read_text_prefix(path, MAX_READ_BYTES)
+}
+
+fn read_text_prefix(path: &Path, max_bytes: u64) -> anyhow::Result<String> {
+    let mut bytes = Vec::new();
+    File::open(path)?
+        .take(max_bytes)
+        .read_to_end(&mut bytes)?;
+
+    // A byte-order mark says how it's encoded; UTF-16 is full of NUL bytes,
+    // so this has to come before the binary check.
+    if let Some((encoding, bom_len)) = Encoding::for_bom(&bytes) {
+        let (decoded, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        if decoded.chars().take(8192).any(|c| c == '\0') {
+            anyhow::bail!("looks like a binary file");
+        }
+        return Ok(decoded.into_owned());
+    }
+
+    // A NUL byte in the first few KB almost always means a binary file
+    // that happens to carry a text extension.
+    if bytes.iter().take(8192).any(|b| *b == 0) {
+        anyhow::bail!("looks like a binary file");
+    }
+
+    // Fast path: valid UTF-8 (possibly cut mid-character by the size cap)
+    match std::str::from_utf8(&bytes) {
+        Ok(s) => return Ok(s.to_string()),
+        Err(e) if e.error_len().is_none() => {
+            return Ok(String::from_utf8_lossy(&bytes[..e.valid_up_to()]).to_string());
+        }
+        Err(_) => {}
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(&bytes, true);
+    let encoding = detector.guess(None, true);
+    let (decoded, _, _) = encoding.decode(&bytes);
+
+    Ok(decoded.into_owned())
+}
+
+/// Extract text from the first `max_pages` pages of a PDF, keeping page numbers.
+///
+/// Pages without any text (scans, pure images) are left out.
+pub fn extract_pdf_pages(path: &Path, max_pages: usize) -> anyhow::Result<Vec<(u32, String)>> {
+    pdf_pages(path, max_pages).map(|(pages, _)| pages)
+}
+
+/// The pages `extract_pdf_pages` returns, plus the PDF's total page count.
+fn pdf_pages(path: &Path, max_pages: usize) -> anyhow::Result<(Vec<(u32, String)>, usize)> {
+    if fs::metadata(path)?.len() > MAX_PDF_BYTES {
+        anyhow::bail!("PDF too large to extract");
+    }
+
+    // pdf-extract panics on some malformed files; treat that as a normal failure.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| -> anyhow::Result<(Vec<(u32, String)>, usize)> {
+        let mut doc = Document::load(path)?;
+        if doc.is_encrypted() {
+            doc.decrypt("")?;
+        }
+
+        let all_pages = doc.get_pages();
+        let page_nums: Vec<u32> = all_pages.keys().copied().take(max_pages).collect();
+
+        let mut pages = Vec::new();
+        for page_num in page_nums {
+            let mut body = String::new();
+            {
+                let mut output = PlainTextOutput::new(&mut body);
+                output_doc_page(&doc, &mut output, page_num)?;
+            }
+            if !body.trim().is_empty() {
+                pages.push((page_num, body));
+            }
+        }
+        Ok((pages, all_pages.len()))
+    }));
+
+    match result {
+        Ok(pages) => pages,
+        Err(_) => anyhow::bail!("PDF parser crashed"),
+    }
+}
+
+/// Collapse runs of whitespace so code indentation doesn't eat the budget.
+fn normalize_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn truncate_chars(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((idx, _)) => s[..idx].to_string(),
+        None => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(bytes: &[u8]) -> anyhow::Result<String> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("export.csv");
+        fs::write(&path, bytes)?;
+        read_text_file(&path)
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes()));
+        bytes
+    }
+
+    #[test]
+    fn utf16_with_bom_is_decoded() {
+        assert_eq!(read(&utf16le("name;amount\nmiete;800")).unwrap(), "name;amount\nmiete;800");
+
+        let mut be = vec![0xFE, 0xFF];
+        be.extend("größe".encode_utf16().flat_map(|u| u.to_be_bytes()));
+        assert_eq!(read(&be).unwrap(), "größe");
+    }
+
+    #[test]
+    fn utf8_bom_is_dropped() {
+        assert_eq!(read(b"\xEF\xBB\xBFhello").unwrap(), "hello");
+    }
+
+    #[test]
+    fn nul_bytes_without_a_bom_mean_binary() {
+        assert!(read(b"PK\x03\x04\x00\x00binary").is_err());
+        assert!(read(&utf16le("a\0b")).is_err());
+    }
+
+    #[test]
+    fn legacy_encodings_are_detected() {
+        let (latin1, _, _) = encoding_rs::WINDOWS_1252.encode("Prüfung bestanden, Gebühren bezahlt");
+        assert_eq!(read(&latin1).unwrap(), "Prüfung bestanden, Gebühren bezahlt");
+    }
+}
@@ -0,0 +1,432 @@
+use crate::ann;
+use crate::config::Config;
+use crate::embedding;
+use crate::indexer::{file_state, index_file};
+use crate::path_filter::human_size;
+use crate::trash;
+use crate::vector_db::{JournalEntry, VectorDB};
+use chrono::{DateTime, Local};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Ask a yes/no question on the terminal. Anything but "y"/"yes" is a no.
+pub fn confirm(question: &str) -> anyhow::Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Show the files, ask, then move them to the trash and forget them in the
+/// index. The batch is journaled so `undo` can bring it back.
+pub fn trash_files(paths: &[PathBuf]) -> anyhow::Result<()> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if !trash::is_trashable(path) {
+            println!("⚠ Skipping {}: not a file", path.display());
+        } else if !files.contains(path) {
+            files.push(path.clone());
+        }
+    }
+    if files.is_empty() {
+        println!("Nothing to delete.");
+        return Ok(());
+    }
+
+    println!("🗑 To be moved to the Trash:");
+    for path in &files {
+        match fs::read_link(path) {
+            Ok(target) => println!("  {}  (link to {})", path.display(), target.display()),
+            Err(_) => {
+                let size = fs::metadata(path).map(|m| human_size(m.len())).unwrap_or_default();
+                println!("  {}  ({})", path.display(), size);
+            }
+        }
+    }
+    if !confirm(&format!("Move {} to the Trash?", count(files.len())))? {
+        println!("Cancelled. Nothing was deleted.");
+        return Ok(());
+    }
+
+    let db = VectorDB::new("meow_vectors.db")?;
+    let mut entries = Vec::new();
+    for path in &files {
+        match trash::trash_file(path) {
+            Ok(t) => {
+                if let Err(e) = db.delete_path(&t.original.to_string_lossy()) {
+                    println!("⚠ {} stays in the index until the next `index`: {e}", path.display());
+                }
+                entries.push(JournalEntry {
+                    action: "trash".to_string(),
+                    source: t.original.to_string_lossy().to_string(),
+                    dest: t.trashed.to_string_lossy().to_string(),
+                    extra: Some(t.info.to_string_lossy().to_string()),
+                });
+            }
+            Err(e) => println!("⚠ Could not trash {}: {e}", path.display()),
+        }
+    }
+
+    if !entries.is_empty() {
+        db.log_batch(&entries, Local::now().timestamp())?;
+        println!("Moved {} to the Trash. Type `undo` to restore.", count(entries.len()));
+    }
+    Ok(())
+}
+
+/// What `run_transfer` does with each planned pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    Move,
+    Copy,
+    Rename,
+}
+
+impl Transfer {
+    fn name(self) -> &'static str {
+        match self {
+            Transfer::Move => "move",
+            Transfer::Copy => "copy",
+            Transfer::Rename => "rename",
+        }
+    }
+
+    fn verb(self) -> &'static str {
+        match self {
+            Transfer::Move => "Move",
+            Transfer::Copy => "Copy",
+            Transfer::Rename => "Rename",
+        }
+    }
+}
+
+/// Pair each file with its place in `dir`. A name that is taken, on disk or
+/// earlier in the plan, gets a " (2)"-style suffix instead of overwriting.
+pub fn plan_into(files: &[PathBuf], dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut plan: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for file in files {
+        let Some(name) = file.file_name() else {
+            continue;
+        };
+        if file.parent() == Some(dir) || plan.iter().any(|(from, _)| from == file) {
+            continue;
+        }
+        let taken: Vec<&PathBuf> = plan.iter().map(|(_, to)| to).collect();
+        let to = free_name(&dir.join(name), &taken);
+        plan.push((file.clone(), to));
+    }
+    plan
+}
+
+/// `path`, or the first "name (n).ext" next to it that isn't in use.
+fn free_name(path: &Path, taken: &[&PathBuf]) -> PathBuf {
+    let in_use = |p: &Path| p.exists() || taken.iter().any(|t| t.as_path() == p);
+    if !in_use(path) {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|p| !in_use(p))
+        .expect("unbounded search for a free name")
+}
+
+/// Print the plan (a dry run), ask, then carry it out. Index rows follow the
+/// files, and the batch is journaled for `undo`.
+pub fn run_transfer(kind: Transfer, plan: &[(PathBuf, PathBuf)]) -> anyhow::Result<()> {
+    if plan.is_empty() {
+        println!("Nothing to {}.", kind.name());
+        return Ok(());
+    }
+
+    println!("📦 Plan (nothing changed yet):");
+    let mut new_dirs: Vec<&Path> = Vec::new();
+    for (from, to) in plan {
+        let renamed = from.file_name() != to.file_name() && kind != Transfer::Rename;
+        let note = if renamed { "  (name taken, renamed)" } else { "" };
+        println!("  {} → {}{}", from.display(), to.display(), note);
+        if let Some(dir) = to.parent()
+            && !dir.exists()
+            && !new_dirs.contains(&dir)
+        {
+            new_dirs.push(dir);
+        }
+    }
+    for dir in &new_dirs {
+        println!("  + new folder {}", dir.display());
+    }
+
+    if !confirm(&format!("{} {}?", kind.verb(), count(plan.len())))? {
+        println!("Cancelled. Nothing was changed.");
+        return Ok(());
+    }
+
+    let db = VectorDB::new("meow_vectors.db")?;
+    let mut entries = Vec::new();
+    for (from, to) in plan {
+        match transfer_one(&db, kind, from, to) {
+            Ok(extra) => entries.push(JournalEntry {
+                action: kind.name().to_string(),
+                source: from.to_string_lossy().to_string(),
+                dest: to.to_string_lossy().to_string(),
+                extra,
+            }),
+            Err(e) => println!("⚠ Could not {} {}: {e}", kind.name(), from.display()),
+        }
+    }
+
+    if !entries.is_empty() {
+        db.log_batch(&entries, Local::now().timestamp())?;
+        println!("Done: {}. Type `undo` to reverse it.", count(entries.len()));
+    }
+    Ok(())
+}
+
+/// Carry out one pair; for a copy, returns what to journal about it. Once
+/// the file is in place it counts as done: a failed index update is only
+/// reported, since `index` repairs it and `undo` must still see the entry.
+fn transfer_one(db: &VectorDB, kind: Transfer, from: &Path, to: &Path) -> anyhow::Result<Option<String>> {
+    // Checked again: the plan may be stale by the time it was confirmed
+    if to.exists() {
+        anyhow::bail!("{} appeared in the meantime", to.display());
+    }
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let (old, new) = (from.to_string_lossy(), to.to_string_lossy());
+    let (indexed, extra) = match kind {
+        Transfer::Move | Transfer::Rename => {
+            trash::move_file(from, to)?;
+            let indexed = file_state(to).and_then(|state| db.rename_path(&old, &new, state));
+            (indexed, None)
+        }
+        Transfer::Copy => {
+            fs::copy(from, to)?;
+            let state = file_state(to)?;
+            let indexed = db.copy_path(&old, &new, state);
+            (indexed, Some(format!("{} {}", state.size, state.modified)))
+        }
+    };
+    if let Err(e) = indexed {
+        println!("⚠ Could not update the index for {} ({e}). Run `index` to catch up.", to.display());
+    }
+    Ok(extra)
+}
+
+/// Reverse the most recent batch of file operations.
+pub fn undo_last() -> anyhow::Result<()> {
+    undo_batch(&VectorDB::new("meow_vectors.db")?)
+}
+
+fn undo_batch(db: &VectorDB) -> anyhow::Result<()> {
+    let Some((at, entries)) = db.last_batch()? else {
+        println!("Nothing to undo.");
+        return Ok(());
+    };
+
+    let when = DateTime::from_timestamp(at, 0)
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    println!("Undoing {} from {}:", count(entries.len()), when);
+
+    let mut restored = Vec::new();
+    let mut failed = 0;
+    for (id, e) in entries.iter().rev() {
+        let (source, dest) = (Path::new(&e.source), Path::new(&e.dest));
+        let result = match e.action.as_str() {
+            "trash" => trash::restore(dest, Path::new(e.extra.as_deref().unwrap_or_default()), source)
+                .map(|_| restored.push(source.to_path_buf())),
+            "move" | "rename" => move_back(db, dest, source),
+            "copy" => remove_copy(db, dest, e.extra.as_deref()),
+            other => Err(anyhow::anyhow!("don't know how to undo {:?}", other)),
+        };
+        match result {
+            Ok(()) if e.action == "copy" => {}
+            Ok(()) => println!("  ↩ {}", e.source),
+            Err(e) => {
+                println!("  ⚠ {e}");
+                failed += 1;
+                continue;
+            }
+        }
+        db.mark_undone(*id)?;
+    }
+    // Failed entries stay in the journal, so the next `undo` retries them
+    if failed > 0 {
+        println!("{} could not be undone. Fix that and run `undo` again.", count(failed));
+    }
+
+    reindex(db, &restored);
+    Ok(())
+}
+
+fn move_back(db: &VectorDB, from: &Path, to: &Path) -> anyhow::Result<()> {
+    if to.exists() {
+        anyhow::bail!("{} exists again; not overwriting it", to.display());
+    }
+    if !from.exists() {
+        anyhow::bail!("{} is gone", from.display());
+    }
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    trash::move_file(from, to)?;
+    let (old, new) = (from.to_string_lossy(), to.to_string_lossy());
+    let indexed = file_state(to).and_then(|state| db.rename_path(&old, &new, state));
+    if let Err(e) = indexed {
+        println!("  ⚠ Could not update the index for {} ({e}). Run `index` to catch up.", to.display());
+    }
+    Ok(())
+}
+
+/// Delete a copy meow made, unless it was edited since: then it goes to the
+/// Trash, so undo never destroys work.
+fn remove_copy(db: &VectorDB, copy: &Path, journaled: Option<&str>) -> anyhow::Result<()> {
+    let state = file_state(copy)?;
+    if journaled == Some(format!("{} {}", state.size, state.modified).as_str()) {
+        fs::remove_file(copy)?;
+        println!("  ✗ {} (copy removed)", copy.display());
+    } else {
+        trash::trash_file(copy)?;
+        println!("  🗑 {} (changed since it was copied; moved to the Trash)", copy.display());
+    }
+    db.delete_path(&copy.to_string_lossy())
+}
+
+/// Put restored files back into search; `index` catches up if this fails.
+/// Symlinks aren't indexed, as in a walk.
+fn reindex(db: &VectorDB, paths: &[PathBuf]) {
+    let paths: Vec<&PathBuf> = paths.iter().filter(|p| !p.is_symlink()).collect();
+    if paths.is_empty() {
+        return;
+    }
+    let loaded = Config::load().and_then(|c| Ok((embedding::from_config(&c.embedding)?, c)));
+    let (embedder, config) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            println!("⚠ Could not re-index ({e}). Run `index` to make them searchable again.");
+            return;
+        }
+    };
+    for path in paths {
+        if let Err(e) = index_file(db, embedder.as_ref(), path, config.walk.max_file_size) {
+            println!("⚠ Could not re-index {} ({e}). Run `index` later.", path.display());
+        }
+    }
+    if let Err(e) = ann::assign_unclustered(db) {
+        eprintln!("Could not update approximate index: {e}");
+    }
+}
+
+fn count(n: usize) -> String {
+    format!("{} {}", n, if n == 1 { "file" } else { "files" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(action: &str, source: &Path, dest: &Path, extra: Option<String>) -> JournalEntry {
+        JournalEntry {
+            action: action.to_string(),
+            source: source.to_string_lossy().to_string(),
+            dest: dest.to_string_lossy().to_string(),
+            extra,
+        }
+    }
+
+    #[test]
+    fn undo_leaves_failed_entries_for_retry() {
+        let tmp = TempDir::new().unwrap();
+        let db = VectorDB::new(":memory:").unwrap();
+        let (a, b) = (tmp.path().join("a.txt"), tmp.path().join("b.txt"));
+        let moved = tmp.path().join("moved");
+        fs::create_dir(&moved).unwrap();
+        let (a_to, b_to) = (moved.join("a.txt"), moved.join("b.txt"));
+        fs::write(&a_to, "a").unwrap();
+        fs::write(&b_to, "b").unwrap();
+        let copy = tmp.path().join("c copy.txt");
+        fs::write(&copy, "c").unwrap();
+        let state = file_state(&copy).unwrap();
+        let journaled = Some(format!("{} {}", state.size, state.modified));
+        db.log_batch(
+            &[
+                entry("move", &a, &a_to, None),
+                entry("move", &b, &b_to, None),
+                entry("copy", &tmp.path().join("c.txt"), &copy, journaled),
+            ],
+            0,
+        )
+        .unwrap();
+
+        // Something new took a.txt's old place, so that move can't go back
+        fs::write(&a, "new").unwrap();
+        undo_batch(&db).unwrap();
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+        assert!(!copy.exists());
+        assert_eq!(fs::read_to_string(&a).unwrap(), "new");
+        assert!(a_to.exists());
+
+        let (_, open) = db.last_batch().unwrap().unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].1.dest, a_to.to_string_lossy());
+
+        // Once the way is clear, the next undo finishes the batch
+        fs::remove_file(&a).unwrap();
+        undo_batch(&db).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "a");
+        assert!(db.last_batch().unwrap().is_none());
+    }
+
+    #[test]
+    fn taken_names_get_a_numbered_suffix() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("dest");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("report.pdf"), "").unwrap();
+        fs::write(dir.join("report (2).pdf"), "").unwrap();
+        fs::write(dir.join("notes"), "").unwrap();
+
+        assert_eq!(free_name(&dir.join("report.pdf"), &[]), dir.join("report (3).pdf"));
+        assert_eq!(free_name(&dir.join("notes"), &[]), dir.join("notes (2)"));
+        assert_eq!(free_name(&dir.join("new.txt"), &[]), dir.join("new.txt"));
+        let planned = dir.join("new.txt");
+        assert_eq!(free_name(&dir.join("new.txt"), &[&planned]), dir.join("new (2).txt"));
+    }
+
+    #[test]
+    fn plan_avoids_clashes_within_itself() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("dest");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("a.txt"), "").unwrap();
+        let (x, y) = (tmp.path().join("x"), tmp.path().join("y"));
+
+        let files = [
+            x.join("a.txt"),
+            y.join("a.txt"),
+            x.join("b.txt"),
+            y.join("b.txt"),
+            x.join("b.txt"),
+            dir.join("a.txt"),
+        ];
+        let plan = plan_into(&files, &dir);
+        assert_eq!(
+            plan,
+            [
+                (x.join("a.txt"), dir.join("a (2).txt")),
+                (y.join("a.txt"), dir.join("a (3).txt")),
+                (x.join("b.txt"), dir.join("b.txt")),
+                (y.join("b.txt"), dir.join("b (2).txt")),
+            ]
+        );
+    }
+}
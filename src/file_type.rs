@@ -0,0 +1,97 @@
+use infer::MatcherType;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Categories a file can be filed under; stored in the index.
+pub const CATEGORIES: &[&str] = &[
+    "pdf", "image", "text", "document", "spreadsheet", "presentation", "audio", "video",
+    "archive", "code", "other",
+];
+
+/// Category of a file, from its magic bytes when they are recognised and its
+/// extension otherwise. Extensionless files that read as text count as text.
+pub fn detect_category(path: &Path) -> &'static str {
+    if let Ok(Some(kind)) = infer::get_from_path(path) {
+        let by_ext = category_for_ext(kind.extension());
+        if by_ext != "other" {
+            return by_ext;
+        }
+        match kind.matcher_type() {
+            MatcherType::Image => return "image",
+            MatcherType::Audio => return "audio",
+            MatcherType::Video => return "video",
+            MatcherType::Archive => return "archive",
+            MatcherType::Book | MatcherType::Doc => return "document",
+            MatcherType::Text => return "text",
+            _ => {}
+        }
+    }
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => category_for_ext(&ext.to_lowercase()),
+        None if looks_like_text(path) => "text",
+        None => "other",
+    }
+}
+
+/// Category for a lower-case extension (without the dot).
+pub fn category_for_ext(ext: &str) -> &'static str {
+    match ext {
+        "pdf" => "pdf",
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "tif" | "tiff" | "heic" | "heif"
+        | "svg" | "ico" | "avif" | "raw" | "cr2" | "nef" | "dng" | "psd" => "image",
+        "txt" | "md" | "markdown" | "rst" | "org" | "log" | "rtf" | "tex" => "text",
+        "doc" | "docx" | "odt" | "pages" | "epub" | "mobi" => "document",
+        "xls" | "xlsx" | "ods" | "numbers" | "csv" | "tsv" => "spreadsheet",
+        "ppt" | "pptx" | "odp" | "key" => "presentation",
+        "mp3" | "wav" | "flac" | "ogg" | "oga" | "m4a" | "aac" | "opus" | "wma" | "aiff" => "audio",
+        "mp4" | "mkv" | "mov" | "avi" | "webm" | "wmv" | "flv" | "m4v" | "mpg" | "mpeg" => "video",
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "iso" | "dmg" => "archive",
+        "rs" | "py" | "js" | "ts" | "jsx" | "tsx" | "go" | "java" | "kt" | "c" | "h" | "cpp"
+        | "hpp" | "cs" | "rb" | "php" | "swift" | "sh" | "bash" | "zsh" | "sql" | "html"
+        | "css" | "scss" | "lua" | "json" | "jsonl" | "yaml" | "yml" | "toml" | "ini" | "xml" => "code",
+        _ => "other",
+    }
+}
+
+/// Map the LLM's `file_type` ("pdf", "photos", "excel", "mp3", ...) to a
+/// category. `None` means no filter: missing, "any", or not understood.
+pub fn category_for_query(file_type: &str) -> Option<&'static str> {
+    let ft = file_type.trim().trim_start_matches('.').to_lowercase();
+
+    let category = match ft.trim_end_matches('s') {
+        "" | "any" | "all" | "file" | "everything" => return None,
+        "pdf" => "pdf",
+        "image" | "photo" | "picture" | "pic" | "screenshot" | "img" => "image",
+        "text" | "note" | "plain text" | "txt" => "text",
+        "document" | "doc" | "word" | "ebook" | "book" => "document",
+        "spreadsheet" | "sheet" | "excel" | "table" => "spreadsheet",
+        "presentation" | "slide" | "deck" | "powerpoint" => "presentation",
+        "audio" | "music" | "song" | "sound" | "recording" => "audio",
+        "video" | "movie" | "clip" => "video",
+        "archive" | "compressed" | "zip file" => "archive",
+        "code" | "source" | "source code" | "script" => "code",
+        _ => category_for_ext(&ft),
+    };
+
+    (category != "other").then_some(category)
+}
+
+/// No NUL bytes and valid UTF-8 in the first few KiB.
+fn looks_like_text(path: &Path) -> bool {
+    let mut buf = Vec::new();
+    let read = File::open(path).and_then(|f| f.take(4096).read_to_end(&mut buf));
+    if read.is_err() || buf.is_empty() {
+        return false;
+    }
+
+    if buf.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(&buf) {
+        Ok(_) => true,
+        // A multi-byte character cut off at the end of the buffer
+        Err(e) => e.error_len().is_none(),
+    }
+}
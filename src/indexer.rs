@@ -1,100 +1,489 @@
-use crate::embedding::embed_text;
-use crate::vector_db::VectorDB;
+use crate::ann;
+use crate::chunker::{split_chunks, CHUNK_CHARS, CHUNK_OVERLAP, MAX_CHUNKS_PER_FILE};
+use crate::config::Config;
+use crate::embedding::{self, Embedder};
+use crate::extract::{extract_text, truncate_chars, MAX_TEXT_CHARS};
+use crate::file_type::detect_category;
+use crate::vector_db::ChunkRow;
+use crate::vector_db::{FileState, IndexMeta, VectorDB};
+use crate::walker::walk_files;
 use chrono::Local;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
 use indicatif::{ProgressBar, ProgressStyle};
 
 
-pub fn run_indexer() -> anyhow::Result<()> {
-    println!("Meow indexer started…");
-
-    let mut roots = Vec::new();
+/// Bump whenever `build_representation` or chunking changes what gets
+/// embedded, so old indexes can be told apart from new ones.
+pub const REPR_VERSION: u32 = 3;
 
-    if let Some(home) = dirs::home_dir() {
-        let downloads = home.join("Downloads");
-        let pictures = home.join("OneDrive/Pictures");
+pub fn run_indexer(roots: &[PathBuf], config: &Config, rebuild: bool) -> anyhow::Result<()> {
+    println!("Meow indexer started…");
 
-        if downloads.exists() {
-            println!("➡ Indexing Downloads: {}", downloads.display());
-            roots.push(downloads);
-        }
+    if roots.is_empty() {
+        println!("No index roots found. Add one with `roots add <dir>` or `meow index --root <dir>`.");
+        return Ok(());
+    }
 
-        if pictures.exists() {
-            println!("➡ Indexing Pictures: {}", pictures.display());
-            roots.push(pictures);
-        }
+    for root in roots {
+        println!("➡ Indexing {}", root.display());
     }
 
     let db = VectorDB::new("meow_vectors.db")?;
+    let embedder = embedding::from_config(&config.embedding)?;
+    let embedder = embedder.as_ref();
+
+    if rebuild {
+        // Make sure the embedder works before throwing the old index away
+        embedder
+            .embed_one("meow")
+            .map_err(|e| anyhow::anyhow!("Can't reach the embedder ({e}); the index was left as it is."))?;
+        println!("Rebuilding: dropping all stored vectors.");
+        db.clear()?;
+    }
+    check_index_meta(&db, embedder)?;
 
     let mut files = Vec::new();
-    for root in &roots {
-        collect_files(root, &mut files);
+    for root in roots {
+        files.extend(walk_files(root, &config.walk));
     }
 
     println!("Found {} files.", files.len());
 
-    let total = files.len() as u64;
+    // ---- Work out what actually changed since the last run ----
+    let known = db.load_file_records()?;
+    let mut seen = HashSet::new();
+    let mut added = Vec::new();
+    let mut updated = Vec::new();
+    let mut unchanged = 0usize;
+
+    for path in files {
+        let key = path.to_string_lossy().to_string();
+        let Ok(state) = file_state(&path) else {
+            continue;
+        };
+
+        match known.get(&key) {
+            Some(old) if old.state == state => {
+                unchanged += 1;
+                // Rows from before move detection have no hash yet
+                if old.hash.is_none()
+                    && let Ok(hash) = content_hash(&path)
+                {
+                    db.set_hash(&key, &hash)?;
+                }
+            }
+            Some(_) => updated.push(path),
+            None => added.push(path),
+        }
+        seen.insert(key);
+    }
+
+    // Rows whose file is gone from disk or no longer under our roots
+    let mut gone: HashSet<&String> = known
+        .keys()
+        .filter(|p| !seen.contains(*p))
+        .filter(|p| {
+            let p = Path::new(p);
+            !p.exists() || roots.iter().any(|r| p.starts_with(r))
+        })
+        .collect();
+
+    // A new path with the same content as a gone one is a move: keep the
+    // embedding and just rename the row instead of asking Ollama again.
+    let mut gone_by_hash: HashMap<&str, &String> = gone
+        .iter()
+        .filter_map(|p| known[*p].hash.as_deref().map(|h| (h, *p)))
+        .collect();
+    let mut moved = 0usize;
+
+    if !gone_by_hash.is_empty() {
+        let mut still_new = Vec::new();
+        for path in added {
+            let old = content_hash(&path)
+                .ok()
+                .and_then(|h| gone_by_hash.remove(h.as_str()));
+
+            match old {
+                Some(old) => {
+                    db.rename_path(old, &path.to_string_lossy(), file_state(&path)?)?;
+                    gone.remove(old);
+                    moved += 1;
+                }
+                None => still_new.push(path),
+            }
+        }
+        added = still_new;
+    }
+
+    for path in &gone {
+        db.delete_path(path)?;
+    }
+
+    // Files indexed before the keyword index or file categories existed
+    // only need those filled in, not a new embedding.
+    let has_text = db.text_paths()?;
+    let categories = db.load_categories()?;
+    for path in known.keys() {
+        if !seen.contains(path) || gone.contains(path) {
+            continue;
+        }
+        if !has_text.contains(path) {
+            let body = if too_large(Path::new(path), config.walk.max_file_size) {
+                None
+            } else {
+                extract_text(Path::new(path)).ok().flatten()
+            };
+            db.store_text(path, body.as_deref())?;
+        }
+        if categories.get(path).is_some_and(|c| c.is_none()) {
+            db.set_category(path, detect_category(Path::new(path)))?;
+        }
+    }
+
+    let total = (added.len() + updated.len()) as u64;
+    if total > 0 {
+        println!("Embedding with {}", embedder.model_id());
+    }
 
     let pb = ProgressBar::new(total);
     pb.set_style(
         ProgressStyle::with_template(
-            "{spinner:.green} Indexing: [{bar:40.cyan/blue}] {pos}/{len} ({percent}%)",
+            "{spinner:.green} Indexing: [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {per_sec} ETA {eta}",
         )
         .unwrap()
         .progress_chars("█░"),
     );
 
-    let mut indexed = 0usize;
+    let todo: Vec<&PathBuf> = added.iter().chain(&updated).collect();
+    let opts = &config.indexing;
+    let workers = opts.concurrency.max(1);
+    let batch_size = opts.batch_size.max(1);
+    let max_size = config.walk.max_file_size;
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    let mut failed = 0usize;
+
+    thread::scope(|scope| {
+        // Workers extract and embed; only this thread touches the DB.
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let todo = &todo;
+            let next = &next;
+            scope.spawn(move || {
+                loop {
+                    let start = next.fetch_add(batch_size, Ordering::SeqCst);
+                    if start >= todo.len() {
+                        break;
+                    }
+                    let group = &todo[start..(start + batch_size).min(todo.len())];
+                    for result in prepare_and_embed(embedder, group, batch_size, max_size) {
+                        if tx.send(result).is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+        drop(tx);
 
-    for path in files {
-        if let Err(e) = index_file(&db, &path) {
-            eprintln!("\nSkipped {}: {}", path.display(), e);
+        for (path, result) in rx {
+            let stored = result.and_then(|(file, vectors)| store_file(&db, &file, &vectors));
+            if let Err(e) = stored {
+                eprintln!("\nSkipped {}: {}", path.display(), e);
+                failed += 1;
+            }
             pb.inc(1);
-            continue;
         }
-
-        indexed += 1;
-        pb.inc(1);
-    }
+    });
 
     pb.finish_with_message("Indexing complete");
 
+    ann::maintain(&db)?;
+    db.prune_orphans()?;
 
-    println!("Indexed {} files.", indexed);
+    println!(
+        "Added {}, updated {}, moved {}, unchanged {}, removed {}.",
+        added.len(),
+        updated.len(),
+        moved,
+        unchanged,
+        gone.len()
+    );
+    if failed > 0 {
+        println!("{} files could not be indexed and will be retried next run.", failed);
+    }
     Ok(())
 }
 
-fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-
-            if path.is_file() {
-                out.push(path);
-            } else if path.is_dir() {
-                collect_files(&path, out);
+/// Make sure the DB was built with the configured model, recording it if new.
+///
+/// A different model or dimension is an error (the vectors can't be compared);
+/// an older representation format only earns a warning.
+pub fn check_index_meta(db: &VectorDB, embedder: &dyn Embedder) -> anyhow::Result<()> {
+    let current = IndexMeta {
+        model: embedder.model_id(),
+        dimension: embedder.embed_one("meow")?.len(),
+        repr_version: REPR_VERSION,
+    };
+
+    let stored = match db.load_meta()? {
+        Some(meta) => meta,
+        None => match db.sample_dimension()? {
+            // Fresh database
+            None => {
+                db.store_meta(&current)?;
+                return Ok(());
             }
-        }
+            // Built before we tracked models; assume the current one if it fits
+            Some(dimension) => {
+                let legacy = IndexMeta {
+                    model: current.model.clone(),
+                    dimension,
+                    repr_version: 0,
+                };
+                if dimension == current.dimension {
+                    db.store_meta(&legacy)?;
+                }
+                legacy
+            }
+        },
+    };
+
+    if stored.model != current.model || stored.dimension != current.dimension {
+        anyhow::bail!(
+            "Index was built with {} ({} dims) but the configured model is {} ({} dims). \
+             Run `meow index --rebuild` to re-embed everything.",
+            stored.model,
+            stored.dimension,
+            current.model,
+            current.dimension
+        );
     }
+
+    if stored.repr_version != REPR_VERSION {
+        println!(
+            "⚠ Index uses representation format v{} (current is v{}). \
+             Run `meow index --rebuild` to refresh it.",
+            stored.repr_version, REPR_VERSION
+        );
+    }
+
+    Ok(())
 }
 
-fn index_file(db: &VectorDB, path: &Path) -> anyhow::Result<()> {
+/// Modification time and size, as compared against the DB row.
+pub fn file_state(path: &Path) -> anyhow::Result<FileState> {
     let meta = fs::metadata(path)?;
     let modified = meta
         .modified()
         .map(|m| chrono::DateTime::<Local>::from(m).timestamp())
         .unwrap_or(0);
 
-    let text = build_representation(path)?;
-    let vec = embed_text(&text)?;
+    Ok(FileState {
+        modified,
+        size: meta.len() as i64,
+    })
+}
+
+/// Bytes hashed from each end of a file when fingerprinting it.
+const HASH_SAMPLE_BYTES: u64 = 1024 * 1024;
+
+/// Content fingerprint used to recognise moved and renamed files.
+///
+/// Hashes the size plus the first and last MiB, which is enough to tell
+/// files apart without reading multi-gigabyte videos end to end.
+fn content_hash(path: &Path) -> anyhow::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(len.to_le_bytes());
+
+    let mut buf = Vec::new();
+    (&mut file).take(HASH_SAMPLE_BYTES).read_to_end(&mut buf)?;
+    hasher.update(&buf);
+
+    if len > 2 * HASH_SAMPLE_BYTES {
+        buf.clear();
+        file.seek(SeekFrom::End(-(HASH_SAMPLE_BYTES as i64)))?;
+        file.read_to_end(&mut buf)?;
+        hasher.update(&buf);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// A file that has been read and split up, ready to be embedded.
+struct PreparedFile {
+    path: PathBuf,
+    state: FileState,
+    hash: String,
+    category: &'static str,
+    /// Extracted contents, for the keyword index.
+    body: Option<String>,
+    /// Whole-file representation first, then one text per chunk.
+    texts: Vec<String>,
+    chunks: Vec<(usize, String)>, // (offset, preview)
+}
+
+type Embedded = (PreparedFile, Vec<Vec<f32>>);
+
+/// Index a single file right away (used by the watcher).
+pub fn index_file(
+    db: &VectorDB,
+    embedder: &dyn Embedder,
+    path: &Path,
+    max_size: Option<u64>,
+) -> anyhow::Result<()> {
+    let file = prepare_file(path, max_size)?;
+    let vectors = embedder.embed(&file.texts)?;
+    store_file(db, &file, &vectors)
+}
+
+/// Prepare a group of files and embed all their texts in shared batches.
+///
+/// If a batch fails, the files are retried one by one so a single bad file
+/// doesn't take the rest of the group down with it.
+fn prepare_and_embed(
+    embedder: &dyn Embedder,
+    paths: &[&PathBuf],
+    batch_size: usize,
+    max_size: Option<u64>,
+) -> Vec<(PathBuf, anyhow::Result<Embedded>)> {
+    let mut results = Vec::new();
+    let mut prepared = Vec::new();
+
+    for path in paths {
+        match prepare_file(path, max_size) {
+            Ok(file) => prepared.push(file),
+            Err(e) => results.push(((*path).clone(), Err(e))),
+        }
+    }
+
+    let texts: Vec<String> = prepared.iter().flat_map(|f| f.texts.clone()).collect();
+    let vectors: anyhow::Result<Vec<Vec<f32>>> = texts
+        .chunks(batch_size)
+        .map(|batch| embedder.embed(batch))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map(|batches| batches.into_iter().flatten().collect());
+
+    match vectors {
+        Ok(mut vectors) => {
+            for file in prepared {
+                let rest = vectors.split_off(file.texts.len());
+                let mine = std::mem::replace(&mut vectors, rest);
+                results.push((file.path.clone(), Ok((file, mine))));
+            }
+        }
+        Err(e) if prepared.len() <= 1 => {
+            if let Some(file) = prepared.pop() {
+                results.push((file.path, Err(e)));
+            }
+        }
+        Err(_) => {
+            for file in prepared {
+                let vectors = file
+                    .texts
+                    .chunks(batch_size)
+                    .map(|batch| embedder.embed(batch))
+                    .collect::<anyhow::Result<Vec<_>>>()
+                    .map(|batches| batches.into_iter().flatten().collect());
+                results.push((file.path.clone(), vectors.map(|v| (file, v))));
+            }
+        }
+    }
+
+    results
+}
+
+/// Read and split a file. Contents of files over `max_size` are skipped, so
+/// those are indexed by name, folder and type only.
+fn prepare_file(path: &Path, max_size: Option<u64>) -> anyhow::Result<PreparedFile> {
+    let state = file_state(path)?;
+    let hash = content_hash(path)?;
+    let category = detect_category(path);
+
+    // Actual file contents, when we can read them
+    let body = if too_large(path, max_size) {
+        None
+    } else {
+        match extract_text(path) {
+            Ok(body) => body,
+            Err(e) => {
+                eprintln!("\nCould not read contents of {}: {}", path.display(), e);
+                None
+            }
+        }
+    };
+
+    let mut texts = vec![build_representation(path, body.as_deref())?];
+    let mut chunks = Vec::new();
+
+    // Long documents also get one vector per chunk
+    if let Some(body) = &body
+        && body.chars().count() > CHUNK_CHARS
+    {
+        let header = describe_file(path);
+        for chunk in split_chunks(body, CHUNK_CHARS, CHUNK_OVERLAP, MAX_CHUNKS_PER_FILE) {
+            texts.push(format!("{}Excerpt: {}", header, chunk.text));
+            chunks.push((chunk.offset, truncate_chars(chunk.text, 160)));
+        }
+    }
+
+    Ok(PreparedFile {
+        path: path.to_path_buf(),
+        state,
+        hash,
+        category,
+        body,
+        texts,
+        chunks,
+    })
+}
+
+fn store_file(db: &VectorDB, file: &PreparedFile, vectors: &[Vec<f32>]) -> anyhow::Result<()> {
+    let Some((vec, chunk_vecs)) = vectors.split_first() else {
+        anyhow::bail!("no embeddings returned");
+    };
+
+    let path_str = file.path.to_string_lossy();
+    db.store_embedding(&path_str, vec, file.state, &file.hash)?;
+    db.set_category(&path_str, file.category)?;
+
+    let chunks: Vec<ChunkRow> = file
+        .chunks
+        .iter()
+        .zip(chunk_vecs)
+        .map(|((offset, preview), vector)| ChunkRow {
+            path: path_str.to_string(),
+            offset: *offset,
+            vector: vector.clone(),
+            preview: preview.clone(),
+        })
+        .collect();
+    db.store_chunks(&path_str, &chunks)?;
+    db.store_text(&path_str, file.body.as_deref())?;
 
-    db.store_embedding(&path.to_string_lossy(), &vec, modified)?;
     Ok(())
 }
 
-fn build_representation(path: &Path) -> anyhow::Result<String> {
+fn too_large(path: &Path, max_size: Option<u64>) -> bool {
+    match (max_size, fs::metadata(path)) {
+        (Some(max), Ok(meta)) => meta.len() > max,
+        _ => false,
+    }
+}
+
+/// One-line "what is this file" sentence used as the prefix of every embedding.
+fn describe_file(path: &Path) -> String {
     let file_name = path
         .file_stem()
         .and_then(|n| n.to_str())
@@ -113,13 +502,27 @@ fn build_representation(path: &Path) -> anyhow::Result<String> {
         .and_then(|n| n.to_str())
         .unwrap_or("");
 
-    let mut text = String::new();
-
-    // Base semantic description
-    text.push_str(&format!(
+    format!(
         "This is a {} file named {} located in {} folder. ",
         ext, file_name, folder
-    ));
+    )
+}
+
+fn build_representation(path: &Path, body: Option<&str>) -> anyhow::Result<String> {
+    let file_name = path
+        .file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .replace(['_', '-', '.'], " ");
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    // Base semantic description
+    let mut text = describe_file(path);
 
     // Add semantic hints by file type
     match ext.as_str() {
@@ -141,19 +544,35 @@ fn build_representation(path: &Path) -> anyhow::Result<String> {
 
         "pdf" => {
             text.push_str("This file is a PDF document. ");
+            if file_name.contains("invoice") || file_name.contains("receipt") {
+                text.push_str("It may be an invoice or receipt. ");
+            }
         }
 
         "txt" | "md" => {
             text.push_str("This file is a text document. ");
         }
 
+        "csv" | "tsv" | "json" => {
+            text.push_str("This file contains structured data. ");
+        }
+
         "exe" | "msi" => {
             text.push_str("This file is an installer or application. ");
         }
 
+        _ if crate::extract::is_text_ext(&ext) => {
+            text.push_str("This file is source code or a config file. ");
+        }
+
         _ => {}
     }
 
+    if let Some(body) = body {
+        text.push_str("Contents: ");
+        text.push_str(&truncate_chars(body, MAX_TEXT_CHARS));
+    }
+
     Ok(text)
 }
 
@@ -7,9 +7,23 @@ mod similarity;
 mod indexer;
 mod types;
 mod ai_decider;
+mod extract;
+mod chunker;
+mod config;
+mod walker;
+mod watcher;
+mod ann;
+mod time_filter;
+mod file_type;
+mod path_filter;
+mod preview;
+mod summarize;
+mod trash;
+mod file_ops;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 /// meow — AI-augmented filesystem shell (MVP)
 #[derive(Parser, Debug)]
@@ -23,12 +37,30 @@ struct Cli {
     /// Optional subcommand. If omitted, starts interactive meow shell.
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Print how each search result was scored (shell or `search`)
+    #[arg(long, global = true)]
+    explain: bool,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Start the interactive Meow shell
     Shell,
+    /// Search once and print the results (for scripts and editors)
+    Search {
+        /// What to look for
+        query: String,
+        /// Directory to search instead of the configured roots (repeatable)
+        #[arg(long = "root", value_name = "DIR")]
+        roots: Vec<String>,
+        /// Maximum number of results
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// Print results as JSON on stdout (progress goes to stderr)
+        #[arg(long)]
+        json: bool,
+    },
     /// Just print a one-off message (for testing)
     #[command(alias = "hi")]
     Hello {
@@ -36,7 +68,49 @@ enum Commands {
         #[arg(default_value = "human")]
         name: String,
     },
-    Index,
+    /// Build or update the semantic index
+    Index {
+        /// Directory to index instead of the configured roots (repeatable)
+        #[arg(long = "root", value_name = "DIR")]
+        roots: Vec<String>,
+        /// Drop all stored vectors and re-embed everything (e.g. after changing model).
+        /// Covers every configured root, so it can't be combined with --root.
+        #[arg(long, conflicts_with = "roots")]
+        rebuild: bool,
+    },
+    /// Watch the index roots and keep the index up to date
+    Watch {
+        /// Directory to watch instead of the configured roots (repeatable)
+        #[arg(long = "root", value_name = "DIR")]
+        roots: Vec<String>,
+    },
+    /// Measure recall of approximate search against exact search
+    #[command(hide = true)]
+    BenchAnn {
+        /// Number of sampled query vectors
+        #[arg(long, default_value_t = 100)]
+        queries: usize,
+        /// Results compared per query (recall@k)
+        #[arg(long, default_value_t = 10)]
+        k: usize,
+    },
+}
+
+/// `--root` arguments if given, otherwise the configured roots. A `--root`
+/// that isn't a directory is an error rather than silently skipped.
+fn resolve_roots(config: &config::Config, args: &[String]) -> Result<Vec<PathBuf>> {
+    if args.is_empty() {
+        return Ok(config.existing_roots());
+    }
+    args.iter()
+        .map(|r| {
+            let path = config::expand_path(r);
+            if !path.is_dir() {
+                anyhow::bail!("Not a directory: {}", path.display());
+            }
+            Ok(path)
+        })
+        .collect()
 }
 
 fn main() -> Result<()> {
@@ -45,13 +119,42 @@ fn main() -> Result<()> {
     match cli.command {
         Some(Commands::Shell) | None => {
             // Default is interactive shell
-            shell::run_shell()?;
+            shell::run_shell(cli.explain)?;
+        }
+        Some(Commands::Search { query, roots, limit, json }) => {
+            let config = config::Config::load()?;
+            let scope = engine::SearchScope {
+                roots: resolve_roots(&config, &roots)?,
+                limit,
+                quiet: json,
+            };
+            let action = ai::AiAction {
+                intent: "search".to_string(),
+                query: Some(query),
+                ..Default::default()
+            };
+            let results = engine::execute_search(action, cli.explain, &scope)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results.to_json())?);
+            }
         }
         Some(Commands::Hello { name }) => {
             println!("Meow, {name}!");
         }
-        Some(Commands::Index) => {
-            indexer::run_indexer()?;
+        Some(Commands::Index { roots, rebuild }) => {
+            let config = config::Config::load()?;
+            indexer::run_indexer(&resolve_roots(&config, &roots)?, &config, rebuild)?;
+        }
+        Some(Commands::Watch { roots }) => {
+            let config = config::Config::load()?;
+            watcher::run_watcher(&resolve_roots(&config, &roots)?, &config)?;
+        }
+        Some(Commands::BenchAnn { queries, k }) => {
+            let config = config::Config::load()?;
+            let db = vector_db::VectorDB::new("meow_vectors.db")?;
+            let nprobe = config.search.nprobe;
+            let recall = ann::bench_recall(&db, queries, k, nprobe)?;
+            println!("recall@{k} with nprobe={nprobe}: {:.3} over {queries} queries", recall);
         }
     }
 
@@ -0,0 +1,279 @@
+use crate::ai::AiAction;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Size, extension and glob restrictions from a search action.
+pub struct PathFilter {
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    extensions: Vec<String>,
+    include: Option<Globs>,
+    exclude: Option<Globs>,
+}
+
+/// Compiled glob patterns, kept with their source text for display.
+struct Globs {
+    written: GlobSet,  // the patterns as given
+    anywhere: GlobSet, // "**/"-prefixed forms of unanchored patterns
+    patterns: Vec<String>,
+}
+
+impl PathFilter {
+    /// `None` when the action asks for none of these filters. Globs that
+    /// don't compile are left out and described in the returned warnings.
+    pub fn from_action(action: &AiAction) -> anyhow::Result<(Option<Self>, Vec<String>)> {
+        let size = |s: &Option<String>| -> anyhow::Result<Option<u64>> {
+            match s {
+                Some(s) => parse_size(s)
+                    .map(Some)
+                    .ok_or_else(|| anyhow::anyhow!("Don't understand the size {:?}", s)),
+                None => Ok(None),
+            }
+        };
+
+        let mut warnings = Vec::new();
+        let filter = Self {
+            min_size: size(&action.min_size)?,
+            max_size: size(&action.max_size)?,
+            extensions: action
+                .extensions
+                .iter()
+                .flatten()
+                .map(|e| e.trim_start_matches("*.").trim_start_matches('.').to_lowercase())
+                .collect(),
+            include: build_globs(action.include_globs.as_deref(), &mut warnings)?,
+            exclude: build_globs(action.exclude_globs.as_deref(), &mut warnings)?,
+        };
+
+        let empty = filter.min_size.is_none()
+            && filter.max_size.is_none()
+            && filter.extensions.is_empty()
+            && filter.include.is_none()
+            && filter.exclude.is_none();
+        Ok(((!empty).then_some(filter), warnings))
+    }
+
+    /// Globs are tried against the path relative to `root`, the absolute
+    /// path and the bare file name, so "*.csv", "projects/" and
+    /// "/home/me/projects/**" all do what you'd expect. Matching at any depth
+    /// only looks below `root`, so "old/" doesn't catch everything in ~/old.
+    pub fn matches(&self, path: &Path, root: &Path) -> bool {
+        if !self.extensions.is_empty() {
+            let ext = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("")
+                .to_lowercase();
+            if !self.extensions.contains(&ext) {
+                return false;
+            }
+        }
+
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let name = path.file_name().map(Path::new).unwrap_or(path);
+        let hit = |globs: &Globs| {
+            globs.written.is_match(relative)
+                || globs.written.is_match(path)
+                || globs.written.is_match(name)
+                || globs.anywhere.is_match(relative)
+        };
+
+        if let Some(include) = &self.include
+            && !hit(include)
+        {
+            return false;
+        }
+        if let Some(exclude) = &self.exclude
+            && hit(exclude)
+        {
+            return false;
+        }
+
+        if self.min_size.is_some() || self.max_size.is_some() {
+            let Ok(meta) = fs::metadata(path) else {
+                return false;
+            };
+            let size = meta.len();
+            if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl fmt::Display for PathFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(min) = self.min_size {
+            parts.push(format!("≥ {}", human_size(min)));
+        }
+        if let Some(max) = self.max_size {
+            parts.push(format!("≤ {}", human_size(max)));
+        }
+        if !self.extensions.is_empty() {
+            parts.push(format!("extension {}", self.extensions.join("/")));
+        }
+        if let Some(globs) = &self.include {
+            parts.push(format!("matching {}", globs.patterns.join(" or ")));
+        }
+        if let Some(globs) = &self.exclude {
+            parts.push(format!("not matching {}", globs.patterns.join(" or ")));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+fn build_globs(patterns: Option<&[String]>, warnings: &mut Vec<String>) -> anyhow::Result<Option<Globs>> {
+    let Some(patterns) = patterns.filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+
+    let (mut written, mut anywhere) = (GlobSetBuilder::new(), GlobSetBuilder::new());
+    let mut kept = Vec::new();
+    for pattern in patterns {
+        // "projects/" means everything below it
+        let p = if pattern.ends_with('/') { format!("{}**", pattern) } else { pattern.clone() };
+        let glob = match Glob::new(&p) {
+            Ok(glob) => glob,
+            Err(e) => {
+                warnings.push(format!("Skipping the glob {:?}: {}", pattern, e.kind()));
+                continue;
+            }
+        };
+        written.add(glob);
+        // Like gitignore, an unanchored pattern matches at any depth, and a
+        // matching folder takes its contents along
+        if !p.starts_with('/') && !p.starts_with("**") {
+            anywhere.add(Glob::new(&format!("**/{}", p))?);
+            if !p.ends_with("**") {
+                anywhere.add(Glob::new(&format!("**/{}/**", p))?);
+            }
+        }
+        kept.push(pattern.clone());
+    }
+
+    if kept.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Globs {
+        written: written.build()?,
+        anywhere: anywhere.build()?,
+        patterns: kept,
+    }))
+}
+
+/// "1GB", "1.5 gb", "500K", "700mb", "2048" (bytes). Units are binary (1 KB = 1024 bytes).
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim().to_lowercase();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier: u64 = match unit.trim().trim_end_matches("ib").trim_end_matches('b') {
+        "" | "byte" | "bytes" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        "t" => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
+
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn globs(include: &[&str], exclude: &[&str]) -> (Option<PathFilter>, Vec<String>) {
+        let list = |g: &[&str]| (!g.is_empty()).then(|| g.iter().map(|s| s.to_string()).collect());
+        let action = AiAction {
+            include_globs: list(include),
+            exclude_globs: list(exclude),
+            ..Default::default()
+        };
+        PathFilter::from_action(&action).unwrap()
+    }
+
+    fn filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        globs(include, exclude).0.expect("some filter")
+    }
+
+    fn kept(filter: &PathFilter, root: &str, path: &str) -> bool {
+        filter.matches(&PathBuf::from(path), Path::new(root))
+    }
+
+    #[test]
+    fn sizes_use_binary_units() {
+        assert_eq!(parse_size("2048"), Some(2048));
+        assert_eq!(parse_size("500K"), Some(500 * 1024));
+        assert_eq!(parse_size("700mb"), Some(700 << 20));
+        assert_eq!(parse_size("1GB"), Some(1 << 30));
+        assert_eq!(parse_size("1 GiB"), Some(1 << 30));
+        assert_eq!(parse_size("1.5 gb"), Some(3 << 29));
+        assert_eq!(parse_size("ten"), None);
+        assert_eq!(parse_size("5 parsecs"), None);
+    }
+
+    #[test]
+    fn unanchored_globs_match_at_any_depth() {
+        let csv = filter(&["*.csv"], &[]);
+        assert!(kept(&csv, "/home/me/data", "/home/me/data/2024/q1/sales.csv"));
+        assert!(!kept(&csv, "/home/me/data", "/home/me/data/2024/q1/sales.txt"));
+    }
+
+    #[test]
+    fn trailing_slash_means_everything_below_that_folder() {
+        let projects = filter(&["projects/"], &[]);
+        assert!(kept(&projects, "/home/me", "/home/me/projects/a.txt"));
+        assert!(kept(&projects, "/home/me", "/home/me/work/projects/deep/a.txt"));
+        assert!(!kept(&projects, "/home/me", "/home/me/projects.txt"));
+        assert!(!kept(&projects, "/home/me", "/home/me/work/a.txt"));
+    }
+
+    #[test]
+    fn folders_above_the_root_are_not_matched() {
+        let not_old = filter(&[], &["old/"]);
+        assert!(kept(&not_old, "/home/me/old/notes", "/home/me/old/notes/a.txt"));
+        assert!(!kept(&not_old, "/home/me/old/notes", "/home/me/old/notes/old/a.txt"));
+
+        let bare = filter(&[], &["old"]);
+        assert!(kept(&bare, "/home/me/old/notes", "/home/me/old/notes/a.txt"));
+        assert!(!kept(&bare, "/home/me/old/notes", "/home/me/old/notes/old/a.txt"));
+    }
+
+    #[test]
+    fn an_invalid_glob_is_skipped_alone() {
+        let (filter, warnings) = globs(&["[bad", "*.csv"], &[]);
+        let filter = filter.expect("the valid glob still applies");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("[bad"));
+        assert!(kept(&filter, "/data", "/data/x.csv"));
+        assert!(!kept(&filter, "/data", "/data/x.txt"));
+
+        let (filter, warnings) = globs(&["[bad"], &[]);
+        assert!(filter.is_none());
+        assert_eq!(warnings.len(), 1);
+    }
+}
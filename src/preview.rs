@@ -0,0 +1,156 @@
+use crate::extract::{extract_pdf_pages, read_text_file, MAX_PDF_PAGES};
+use crate::file_type::detect_category;
+use crate::path_filter::human_size;
+use chrono::{DateTime, Local};
+use console::Term;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::time::SystemTime;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use syntect::util::as_24_bit_terminal_escaped;
+
+const THEME: &str = "base16-ocean.dark";
+
+/// Show a file in the terminal: text and code page by page (code
+/// highlighted), PDFs as extracted text, anything else as a metadata card.
+pub fn show_file(path: &Path) -> anyhow::Result<()> {
+    if !path.is_file() {
+        anyhow::bail!("Not a file: {}", path.display());
+    }
+
+    let category = detect_category(path);
+    println!("📖 {}", path.display());
+
+    match category {
+        "pdf" => {
+            let pages = extract_pdf_pages(path, MAX_PDF_PAGES)?;
+            if pages.iter().all(|(_, text)| text.trim().is_empty()) {
+                println!("(no extractable text; is it a scan?)");
+                return show_card(path, category);
+            }
+            let mut lines = Vec::new();
+            for (page, text) in pages {
+                lines.push(format!("── page {} ──", page));
+                lines.extend(wrap(&text, 100));
+            }
+            page_lines(&lines)
+        }
+        "image" | "audio" | "video" | "archive" => show_card(path, category),
+        // Anything else is worth a try as text; binaries are caught by the NUL check
+        _ => match read_text_file(path) {
+            Ok(text) => page_lines(&render_text(path, &text)),
+            Err(_) => show_card(path, category),
+        },
+    }
+}
+
+/// Text with line numbers, highlighted when it's code going to a terminal.
+fn render_text(path: &Path, text: &str) -> Vec<String> {
+    let width = text.lines().count().to_string().len();
+    let number = |i: usize| format!("{:>width$} │ ", i + 1, width = width);
+
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let syntaxes = SyntaxSet::load_defaults_newlines();
+    let syntax = syntaxes
+        .find_syntax_by_extension(ext)
+        .filter(|s| s.name != "Plain Text" && io::stdout().is_terminal());
+
+    let Some(syntax) = syntax else {
+        return text.lines().enumerate().map(|(i, l)| number(i) + l).collect();
+    };
+
+    let themes = ThemeSet::load_defaults();
+    let mut highlighter = HighlightLines::new(syntax, &themes.themes[THEME]);
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = format!("{}\n", line);
+            match highlighter.highlight_line(&line, &syntaxes) {
+                Ok(ranges) => format!(
+                    "{}{}\x1b[0m",
+                    number(i),
+                    as_24_bit_terminal_escaped(&ranges, false).trim_end_matches('\n')
+                ),
+                Err(_) => number(i) + line.trim_end_matches('\n'),
+            }
+        })
+        .collect()
+}
+
+/// What we can say about a file we can't show.
+fn show_card(path: &Path, category: &str) -> anyhow::Result<()> {
+    let meta = fs::metadata(path)?;
+    let mime = infer::get_from_path(path)
+        .ok()
+        .flatten()
+        .map(|k| k.mime_type().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let time = |t: io::Result<SystemTime>| {
+        t.map(|t| DateTime::<Local>::from(t).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| "n/a".to_string())
+    };
+
+    println!("  name      {}", path.file_name().unwrap_or_default().to_string_lossy());
+    println!("  type      {} ({})", category, mime);
+    println!("  size      {} ({} bytes)", human_size(meta.len()), meta.len());
+    println!("  modified  {}", time(meta.modified()));
+    println!("  created   {}", time(meta.created()));
+    println!("  accessed  {}", time(meta.accessed()));
+    if meta.permissions().readonly() {
+        println!("  read-only");
+    }
+    println!("Use `open <n>` to view it in its app.");
+    Ok(())
+}
+
+/// Print a screenful at a time when attached to a terminal.
+fn page_lines(lines: &[String]) -> anyhow::Result<()> {
+    if lines.is_empty() {
+        println!("(empty file)");
+        return Ok(());
+    }
+
+    let interactive = io::stdout().is_terminal() && io::stdin().is_terminal();
+    let height = Term::stdout().size().0.saturating_sub(2).max(5) as usize;
+    let mut stdin = io::stdin().lock();
+
+    for (n, page) in lines.chunks(height).enumerate() {
+        for line in page {
+            println!("{}", line);
+        }
+
+        let shown = (n + 1) * height;
+        if interactive && shown < lines.len() {
+            print!("-- {}/{} lines, Enter for more, q to stop -- ", shown, lines.len());
+            io::stdout().flush()?;
+            let mut answer = String::new();
+            stdin.read_line(&mut answer)?;
+            if answer.trim().eq_ignore_ascii_case("q") {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Break long lines (extracted PDF text is one line per paragraph or page).
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + word.chars().count() + 1 > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
@@ -2,10 +2,17 @@ use anyhow::Result;
 use rustyline::{error::ReadlineError, DefaultEditor};
 
 use crate::ai::interpret_command;
-use crate::engine::execute_action;
+use crate::config::Config;
+use crate::engine::{execute_action, parse_selection};
+use crate::file_ops::{trash_files, undo_last};
 use crate::indexer::run_indexer;
+use crate::preview::show_file;
+use crate::summarize::summarize_file;
 use crate::types::SearchResults;
+use crate::vector_db::VectorDB;
 
+use chrono::Local;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 fn print_banner() {
@@ -60,7 +67,83 @@ fn clear_terminal() {
     }
 }
 
-pub fn run_shell() -> Result<()> {
+fn handle_roots(args: &str) -> Result<()> {
+    let mut config = Config::load()?;
+    let (cmd, dir) = args.split_once(' ').unwrap_or((args, ""));
+    let dir = dir.trim();
+
+    match cmd {
+        "" | "list" => {
+            if config.roots.is_empty() {
+                println!("No index roots configured.");
+            }
+            for root in &config.roots {
+                let note = if root.is_dir() { "" } else { "  (missing)" };
+                println!("  {}{}", root.display(), note);
+            }
+        }
+        "add" if !dir.is_empty() => {
+            if config.add_root(dir)? {
+                config.save()?;
+                println!("Added root. Run `index` to pick up its files.");
+            } else {
+                println!("Already a root.");
+            }
+        }
+        "remove" | "rm" if !dir.is_empty() => {
+            if config.remove_root(dir) {
+                config.save()?;
+                println!("Removed root.");
+            } else {
+                println!("Not a configured root.");
+            }
+        }
+        _ => println!("Usage: roots [list] | roots add <dir> | roots remove <dir>"),
+    }
+
+    Ok(())
+}
+
+/// Remember that result `n` was opened, and for which query.
+fn record_choice(results: &SearchResults, n: usize) -> Result<()> {
+    let db = VectorDB::new("meow_vectors.db")?;
+    let path = &results.items[n - 1].path;
+    let now = Local::now().timestamp();
+
+    db.record_open(path, now)?;
+    if !results.query_vector.is_empty() {
+        db.record_feedback(&results.query, &results.query_vector, &results.model, path, n, now)?;
+    }
+    Ok(())
+}
+
+fn handle_learned(args: &str) -> Result<()> {
+    let db = VectorDB::new("meow_vectors.db")?;
+
+    match args {
+        "" | "list" => {
+            let summary = db.feedback_summary()?;
+            if summary.is_empty() {
+                println!("Nothing learned yet. Open a result with `open <n>` after a search.");
+            }
+            for (query, path, count, last) in summary {
+                let when = chrono::DateTime::from_timestamp(last, 0)
+                    .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                println!("  \"{}\" → {}  ({}×, last {})", query, path, count, when);
+            }
+        }
+        "reset" => {
+            let removed = db.clear_feedback()?;
+            println!("Forgot {} learned choices and all open counts.", removed);
+        }
+        _ => println!("Usage: learned [list] | learned reset"),
+    }
+
+    Ok(())
+}
+
+pub fn run_shell(mut explain: bool) -> Result<()> {
     let mut last_results: Option<SearchResults> = None;
     let mut rl = DefaultEditor::new()?;
 
@@ -93,15 +176,49 @@ pub fn run_shell() -> Result<()> {
                 }
 
                 // Index
-                if matches!(input, "index" | "reindex") {
+                if matches!(input, "index" | "reindex" | "index --rebuild") {
                     println!("Building semantic index…");
-                    match run_indexer() {
+                    let rebuild = input.ends_with("--rebuild");
+                    let result =
+                        Config::load().and_then(|c| run_indexer(&c.existing_roots(), &c, rebuild));
+                    match result {
                         Ok(_) => println!("Indexing finished.\n"),
                         Err(e) => println!("Indexing failed: {e}"),
                     }
                     continue;
                 }
 
+                // explain / explain on / explain off
+                if input == "explain" || input.starts_with("explain ") {
+                    explain = match input[7..].trim() {
+                        "" => !explain,
+                        "on" => true,
+                        "off" => false,
+                        _ => {
+                            println!("Usage: explain [on|off]");
+                            continue;
+                        }
+                    };
+                    println!("Explain mode {}.", if explain { "on" } else { "off" });
+                    continue;
+                }
+
+                // learned / learned reset
+                if input == "learned" || input.starts_with("learned ") {
+                    if let Err(e) = handle_learned(input[7..].trim()) {
+                        println!("Learned error: {e}");
+                    }
+                    continue;
+                }
+
+                // roots / roots add <dir> / roots remove <dir>
+                if input == "roots" || input.starts_with("roots ") {
+                    if let Err(e) = handle_roots(input[5..].trim()) {
+                        println!("Roots error: {e}");
+                    }
+                    continue;
+                }
+
                 // open <n>
                 if input.to_lowercase().starts_with("open ") {
                     let arg = input[5..].trim();
@@ -120,9 +237,14 @@ pub fn run_shell() -> Result<()> {
                             continue;
                         }
 
-                        let path = &results.items[n - 1];
+                        let path = &results.items[n - 1].path;
                         open_path(path)?;
                         println!("Opened: {}", path);
+
+                        // Feeds the frequency and learned-preference boosts
+                        if let Err(e) = record_choice(results, n) {
+                            eprintln!("Could not record open: {e}");
+                        }
                     } else {
                         println!("Usage: open <number>");
                     }
@@ -130,6 +252,75 @@ pub fn run_shell() -> Result<()> {
                     continue;
                 }
 
+                // read <n> / summarize <n>
+                let lower = input.to_lowercase();
+                if lower.starts_with("read ") || lower.starts_with("summarize ") {
+                    let (cmd, arg) = input.split_once(' ').unwrap_or((input, ""));
+                    let cmd = cmd.to_lowercase();
+
+                    if let Ok(n) = arg.trim().parse::<usize>() {
+                        let Some(results) = &last_results else {
+                            println!("No previous results. Run a search first.");
+                            continue;
+                        };
+
+                        if n == 0 || n > results.items.len() {
+                            println!(
+                                "Invalid index. Choose 1..{}",
+                                results.items.len()
+                            );
+                            continue;
+                        }
+
+                        let path = Path::new(&results.items[n - 1].path);
+                        let result = if cmd == "read" {
+                            show_file(path)
+                        } else {
+                            VectorDB::new("meow_vectors.db").and_then(|db| summarize_file(&db, path).map(|_| ()))
+                        };
+                        if let Err(e) = result {
+                            println!("Cannot {} {}: {e}", cmd, path.display());
+                        }
+                    } else {
+                        println!("Usage: {} <number>", cmd);
+                    }
+
+                    continue;
+                }
+
+                // delete <n> [<n>...] / delete 2-4
+                if lower.starts_with("delete ") {
+                    let Some(numbers) = parse_selection(&input[7..]) else {
+                        println!("Usage: delete <number> [<number>...]");
+                        continue;
+                    };
+                    let Some(results) = &last_results else {
+                        println!("No previous results. Run a search first.");
+                        continue;
+                    };
+                    if let Some(bad) = numbers.iter().find(|&&n| n == 0 || n > results.items.len()) {
+                        println!("Invalid index {}. Choose 1..{}", bad, results.items.len());
+                        continue;
+                    }
+
+                    let paths: Vec<_> = numbers
+                        .iter()
+                        .map(|&n| PathBuf::from(&results.items[n - 1].path))
+                        .collect();
+                    if let Err(e) = trash_files(&paths) {
+                        println!("Delete failed: {e}");
+                    }
+                    continue;
+                }
+
+                // Undo the last delete
+                if input == "undo" {
+                    if let Err(e) = undo_last() {
+                        println!("Undo failed: {e}");
+                    }
+                    continue;
+                }
+
                 // AI command
                 if input.starts_with("ai ") {
                     let query = input.trim_start_matches("ai ").to_string();
@@ -138,7 +329,7 @@ pub fn run_shell() -> Result<()> {
                         Ok(action) => {
                             println!("AI interpreted:\n{:#?}", action);
 
-                            match execute_action(action) {
+                            match execute_action(action, explain, last_results.as_ref()) {
                                 Ok(Some(results)) => {
                                     last_results = Some(results);
                                 }
@@ -156,7 +347,7 @@ pub fn run_shell() -> Result<()> {
                     continue;
                 }
 
-                println!("Unknown command. Try `ai find ...`, `index`, `open <n>`");
+                println!("Unknown command. Try `ai find ...`, `index`, `roots`, `open <n>`, `read <n>`, `summarize <n>`, `delete <n>`, `undo`, `learned`, `explain`");
             }
 
             Err(ReadlineError::Interrupted) => {
@@ -1,4 +1,9 @@
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    // Vectors from different models can't be compared
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
     let mut dot = 0.0;
     let mut norm_a = 0.0;
     let mut norm_b = 0.0;
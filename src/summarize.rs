@@ -0,0 +1,129 @@
+use crate::ai::{GENERATE_MODEL, GENERATE_URL};
+use crate::chunker::split_chunks;
+use crate::extract::extract_for_summary;
+use crate::indexer::file_state;
+use crate::vector_db::VectorDB;
+use reqwest::blocking::Client;
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Text per map step; small enough for an 8k-context model with room for the answer.
+const PART_CHARS: usize = 6000;
+const PART_OVERLAP: usize = 200;
+
+/// Summarize a file with the local LLM, printing the summary as it streams in.
+///
+/// Long files are summarized part by part, then the part summaries are
+/// combined (map-reduce). Results are cached per path and modification time.
+pub fn summarize_file(db: &VectorDB, path: &Path) -> anyhow::Result<String> {
+    let key = path.to_string_lossy();
+    let modified = file_state(path)?.modified;
+
+    if let Some(summary) = db.load_summary(&key, modified)? {
+        println!("📝 Summary of {} (cached):\n", path.display());
+        println!("{}", summary);
+        return Ok(summary);
+    }
+
+    let Some((text, truncated)) = extract_for_summary(path)? else {
+        anyhow::bail!("Can't extract text from {}", path.display());
+    };
+    if text.trim().is_empty() {
+        anyhow::bail!("{} has no text to summarize", path.display());
+    }
+    if truncated {
+        println!(
+            "⚠ {} is too long to read in full; summarizing its first {} characters.",
+            path.display(),
+            text.chars().count()
+        );
+    }
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    // Every part: `extract_for_summary` already bounded the text
+    let parts = split_chunks(&text, PART_CHARS, PART_OVERLAP, usize::MAX);
+    let client = Client::builder().timeout(Duration::from_secs(600)).build()?;
+
+    let material = if parts.len() <= 1 {
+        text.clone()
+    } else {
+        // Map: one short summary per part
+        let mut notes = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            println!("Summarizing part {}/{}…", i + 1, parts.len());
+            let prompt = format!(
+                "Summarize part {} of {} of the file \"{}\" in 3-5 bullet points. \
+                 Keep names, numbers and dates.\n\n{}",
+                i + 1,
+                parts.len(),
+                name,
+                part.text
+            );
+            notes.push(generate(&client, &prompt, false)?);
+        }
+        notes.join("\n\n")
+    };
+
+    // Reduce (or the whole thing, for short files), streamed to the terminal
+    let prompt = if parts.len() <= 1 {
+        format!(
+            "Summarize the file \"{}\" in a short paragraph followed by key points. \
+             Keep names, numbers and dates.\n\n{}",
+            name, material
+        )
+    } else {
+        format!(
+            "These are notes on consecutive parts of the file \"{}\". Combine them into one \
+             summary: a short paragraph followed by key points. Don't mention the parts.\n\n{}",
+            name, material
+        )
+    };
+
+    println!("📝 Summary of {}:\n", path.display());
+    let summary = generate(&client, &prompt, true)?;
+
+    db.store_summary(&key, modified, summary.trim())?;
+    Ok(summary.trim().to_string())
+}
+
+/// Call Ollama's `/api/generate`. With `stream`, tokens are printed as they
+/// arrive (the response is one JSON object per line).
+fn generate(client: &Client, prompt: &str, stream: bool) -> anyhow::Result<String> {
+    let body = json!({
+        "model": GENERATE_MODEL,
+        "prompt": prompt,
+        "stream": stream
+    });
+
+    let resp = client.post(GENERATE_URL).json(&body).send()?.error_for_status()?;
+
+    if !stream {
+        let resp: serde_json::Value = resp.json()?;
+        return Ok(resp["response"].as_str().unwrap_or("").trim().to_string());
+    }
+
+    let mut out = String::new();
+    let mut stdout = io::stdout();
+    for line in BufReader::new(resp).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let chunk: serde_json::Value = serde_json::from_str(&line)?;
+        if let Some(err) = chunk.get("error").and_then(|e| e.as_str()) {
+            anyhow::bail!("Ollama error: {err}");
+        }
+        if let Some(token) = chunk.get("response").and_then(|t| t.as_str()) {
+            print!("{}", token);
+            stdout.flush()?;
+            out.push_str(token);
+        }
+        if chunk.get("done").and_then(|d| d.as_bool()) == Some(true) {
+            break;
+        }
+    }
+    println!();
+    Ok(out)
+}
@@ -0,0 +1,461 @@
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, TimeZone, Timelike, Weekday};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Which of a file's timestamps a filter looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeField {
+    Modified,
+    Created,
+    Accessed,
+}
+
+/// A half-open `[start, end)` window on one of a file's timestamps.
+/// A missing bound is open-ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeFilter {
+    pub field: TimeField,
+    pub start: Option<DateTime<Local>>,
+    pub end: Option<DateTime<Local>>,
+}
+
+type Span = (Option<DateTime<Local>>, Option<DateTime<Local>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl TimeFilter {
+    /// Parse phrases like "yesterday", "last week", "2 days ago", "since monday",
+    /// "in march", "before 2024-03-01" or "created between june and august",
+    /// relative to `now`. Returns `None` if the phrase isn't understood.
+    ///
+    /// "last/past N units" is a rolling window ending now; "this week",
+    /// "in march" and "N units ago" are calendar periods (weeks start Monday).
+    pub fn parse(expr: &str, now: DateTime<Local>) -> Option<Self> {
+        let text = expr.to_lowercase().replace([',', '"', '\''], " ");
+        let mut words: Vec<&str> = text.split_whitespace().collect();
+
+        // "created last week", "last modified yesterday", "opened today"
+        let mut field = TimeField::Modified;
+        if let Some(pos) = words.iter().position(|w| field_word(w).is_some()) {
+            field = field_word(words[pos]).unwrap_or(field);
+            words.remove(pos);
+            if pos > 0 && words[pos - 1] == "last" {
+                words.remove(pos - 1);
+            }
+        }
+        words.retain(|w| !matches!(*w, "in" | "on" | "during" | "the" | "of" | "at" | "files" | "file"));
+
+        let (start, end) = parse_span(&words, now)?;
+        Some(Self { field, start, end })
+    }
+
+    pub fn contains(&self, t: DateTime<Local>) -> bool {
+        self.start.is_none_or(|s| t >= s) && self.end.is_none_or(|e| t < e)
+    }
+
+    /// Whether the file's timestamp falls in the window. Filesystems that
+    /// don't record creation time fall back to modification time.
+    pub fn matches(&self, path: &Path) -> bool {
+        let Ok(meta) = fs::metadata(path) else {
+            return false;
+        };
+        let time = match self.field {
+            TimeField::Modified => meta.modified(),
+            TimeField::Created => meta.created().or_else(|_| meta.modified()),
+            TimeField::Accessed => meta.accessed(),
+        };
+        time.is_ok_and(|t| self.contains(t.into()))
+    }
+}
+
+impl fmt::Display for TimeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = match self.field {
+            TimeField::Modified => "modified",
+            TimeField::Created => "created",
+            TimeField::Accessed => "accessed",
+        };
+        let show = |t: &DateTime<Local>| t.format("%Y-%m-%d %H:%M").to_string();
+
+        match (&self.start, &self.end) {
+            (Some(s), Some(e)) => write!(f, "{} from {} to {}", field, show(s), show(e)),
+            (Some(s), None) => write!(f, "{} since {}", field, show(s)),
+            (None, Some(e)) => write!(f, "{} before {}", field, show(e)),
+            (None, None) => write!(f, "{} any time", field),
+        }
+    }
+}
+
+fn field_word(w: &str) -> Option<TimeField> {
+    match w {
+        "modified" | "edited" | "changed" | "updated" | "saved" => Some(TimeField::Modified),
+        "created" | "made" | "added" | "downloaded" => Some(TimeField::Created),
+        "accessed" | "opened" | "viewed" | "used" => Some(TimeField::Accessed),
+        _ => None,
+    }
+}
+
+fn parse_span(words: &[&str], now: DateTime<Local>) -> Option<Span> {
+    match words {
+        [] => None,
+
+        // Ranges between two points
+        ["between", rest @ ..] | ["from", rest @ ..] => {
+            let split = rest.iter().position(|w| matches!(*w, "and" | "to" | "until" | "till"))?;
+            let (from, _) = parse_span(&rest[..split], now)?;
+            let (_, to) = parse_span(&rest[split + 1..], now)?;
+            Some((from, to))
+        }
+        ["since", rest @ ..] => Some((parse_span(rest, now)?.0, None)),
+        ["after", rest @ ..] => Some((parse_span(rest, now)?.1, None)),
+        ["before", rest @ ..] => Some((None, parse_span(rest, now)?.0)),
+        ["until", rest @ ..] | ["till", rest @ ..] => Some((None, parse_span(rest, now)?.1)),
+
+        // "older than 3 days", "more than a week ago"
+        ["older", "than", rest @ ..] | ["more", "than", rest @ ..] => {
+            let (n, unit) = amount(strip_ago(rest))?;
+            Some((None, Some(shift_back(now, n, unit)?)))
+        }
+        // "newer than 3 days", "less than a week ago", "within 2 weeks"
+        ["newer", "than", rest @ ..] | ["less", "than", rest @ ..] | ["within", rest @ ..] => {
+            let (n, unit) = amount(strip_ago(rest))?;
+            Some((Some(shift_back(now, n, unit)?), None))
+        }
+
+        ["now"] | ["today"] => Some(calendar(now, Unit::Day)),
+        ["yesterday"] => Some(calendar(now - Duration::days(1), Unit::Day)),
+        ["day", "before", "yesterday"] => Some(calendar(now - Duration::days(2), Unit::Day)),
+        ["recent"] | ["recently"] | ["lately"] => Some((Some(now - Duration::days(7)), None)),
+
+        ["this", "morning"] => Some((Some(midnight(now.date_naive())), None)),
+        ["this", "weekend"] => Some(weekend(now)),
+        ["last", "weekend"] => Some(weekend(now - Duration::days(7))),
+        ["this", unit] => Some(calendar(now, parse_unit(unit)?)),
+
+        // "N units ago": the calendar period N units back
+        [.., "ago"] => {
+            let (n, unit) = amount(&words[..words.len() - 1])?;
+            Some(calendar(shift_back(now, n, unit)?, unit))
+        }
+
+        // "last week", "past 3 days", "last 24 hours": rolling window ending now
+        ["last" | "past" | "previous", rest @ ..] if amount(rest).is_some() => {
+            let (n, unit) = amount(rest)?;
+            Some((Some(shift_back(now, n, unit)?), None))
+        }
+
+        // "last monday", "last march"
+        ["last" | "previous", day] if weekday(day).is_some() => {
+            let date = last_weekday(now.date_naive() - Duration::days(1), weekday(day)?);
+            Some(calendar(midnight(date), Unit::Day))
+        }
+        ["last" | "previous", name] if month(name).is_some() => {
+            // The most recent one that is over
+            let m = month(name)?;
+            let year = if m < now.month() { now.year() } else { now.year() - 1 };
+            month_span(year, m)
+        }
+
+        [day] if weekday(day).is_some() => {
+            let date = last_weekday(now.date_naive(), weekday(day)?);
+            Some(calendar(midnight(date), Unit::Day))
+        }
+
+        _ => parse_date(words, now),
+    }
+}
+
+/// Absolute dates: "2024-03-15", "march", "march 2024", "15 march", "march 15th 2024", "2024".
+fn parse_date(words: &[&str], now: DateTime<Local>) -> Option<Span> {
+    match words {
+        [iso] if iso.contains('-') => {
+            let parts: Vec<&str> = iso.split('-').collect();
+            match parts.as_slice() {
+                [y, m, d] => day_span(y.parse().ok()?, m.parse().ok()?, d.parse().ok()?),
+                [y, m] => month_span(y.parse().ok()?, m.parse().ok()?),
+                _ => None,
+            }
+        }
+        [year] if year.len() == 4 && year.parse::<i32>().is_ok() => {
+            let year: i32 = year.parse().ok()?;
+            let start = NaiveDate::from_ymd_opt(year, 1, 1)?;
+            let end = NaiveDate::from_ymd_opt(year + 1, 1, 1)?;
+            Some((Some(midnight(start)), Some(midnight(end))))
+        }
+        // Bare month: the most recent one, this year's if it has started
+        [name] => {
+            let m = month(name)?;
+            month_span(recent_year(now, m), m)
+        }
+        [name, year] if month(name).is_some() && year.len() == 4 => {
+            month_span(year.parse().ok()?, month(name)?)
+        }
+        [name, day] if month(name).is_some() => {
+            day_span(recent_year(now, month(name)?), month(name)?, day_number(day)?)
+        }
+        [day, name] if month(name).is_some() => {
+            day_span(recent_year(now, month(name)?), month(name)?, day_number(day)?)
+        }
+        [name, day, year] if month(name).is_some() => {
+            day_span(year.parse().ok()?, month(name)?, day_number(day)?)
+        }
+        [day, name, year] if month(name).is_some() => {
+            day_span(year.parse().ok()?, month(name)?, day_number(day)?)
+        }
+        _ => None,
+    }
+}
+
+fn strip_ago<'a, 'b>(words: &'a [&'b str]) -> &'a [&'b str] {
+    match words {
+        [rest @ .., "ago"] => rest,
+        _ => words,
+    }
+}
+
+/// "3 days", "a week", "couple of hours", "month" (= one month).
+fn amount(words: &[&str]) -> Option<(u32, Unit)> {
+    match words {
+        [unit] => Some((1, parse_unit(unit)?)),
+        ["a" | "an", "couple" | "few", unit] => Some((count(words[1])?, parse_unit(unit)?)),
+        [n, unit] => Some((count(n)?, parse_unit(unit)?)),
+        _ => None,
+    }
+}
+
+fn count(w: &str) -> Option<u32> {
+    if let Ok(n) = w.parse() {
+        return Some(n);
+    }
+    let n = match w {
+        "a" | "an" | "one" => 1,
+        "two" | "couple" => 2,
+        "three" | "few" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        "twelve" => 12,
+        _ => return None,
+    };
+    Some(n)
+}
+
+fn parse_unit(w: &str) -> Option<Unit> {
+    let unit = match w.trim_end_matches('s') {
+        "min" | "minute" => Unit::Minute,
+        "h" | "hr" | "hour" => Unit::Hour,
+        "day" => Unit::Day,
+        "week" => Unit::Week,
+        "month" => Unit::Month,
+        "year" | "yr" => Unit::Year,
+        _ => return None,
+    };
+    Some(unit)
+}
+
+fn weekday(w: &str) -> Option<Weekday> {
+    match w {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn month(w: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january", "february", "march", "april", "may", "june",
+        "july", "august", "september", "october", "november", "december",
+    ];
+    if w.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|m| m.starts_with(w))
+        .map(|i| i as u32 + 1)
+}
+
+/// "15", "15th", "1st".
+fn day_number(w: &str) -> Option<u32> {
+    w.trim_end_matches(|c: char| c.is_alphabetic()).parse().ok()
+}
+
+/// Year of the most recent occurrence of `month` (this year unless it's still ahead).
+fn recent_year(now: DateTime<Local>, month: u32) -> i32 {
+    if month <= now.month() { now.year() } else { now.year() - 1 }
+}
+
+/// The latest `day` on or before `from`.
+fn last_weekday(from: NaiveDate, day: Weekday) -> NaiveDate {
+    let back = (from.weekday().num_days_from_monday() + 7 - day.num_days_from_monday()) % 7;
+    from - Duration::days(back as i64)
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Local> {
+    let naive = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&naive))
+}
+
+fn day_span(year: i32, month: u32, day: u32) -> Option<Span> {
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+    Some(calendar(midnight(date), Unit::Day))
+}
+
+fn month_span(year: i32, month: u32) -> Option<Span> {
+    let date = NaiveDate::from_ymd_opt(year, month, 1)?;
+    Some(calendar(midnight(date), Unit::Month))
+}
+
+/// Saturday 00:00 to Monday 00:00 of the week containing `t`.
+fn weekend(t: DateTime<Local>) -> Span {
+    let monday = last_weekday(t.date_naive(), Weekday::Mon);
+    let saturday = monday + Duration::days(5);
+    (Some(midnight(saturday)), Some(midnight(saturday + Duration::days(2))))
+}
+
+/// `t` moved `n` units back, or `None` past the range chrono can represent
+/// ("99999 years ago").
+fn shift_back(t: DateTime<Local>, n: u32, unit: Unit) -> Option<DateTime<Local>> {
+    let n64 = i64::from(n);
+    match unit {
+        Unit::Minute => t.checked_sub_signed(Duration::try_minutes(n64)?),
+        Unit::Hour => t.checked_sub_signed(Duration::try_hours(n64)?),
+        Unit::Day => t.checked_sub_signed(Duration::try_days(n64)?),
+        Unit::Week => t.checked_sub_signed(Duration::try_weeks(n64)?),
+        Unit::Month => t.checked_sub_months(Months::new(n)),
+        Unit::Year => t.checked_sub_months(Months::new(n.checked_mul(12)?)),
+    }
+}
+
+/// The calendar minute/hour/day/week/month/year containing `t`.
+fn calendar(t: DateTime<Local>, unit: Unit) -> Span {
+    let date = t.date_naive();
+    let (start, end) = match unit {
+        Unit::Minute => {
+            let start = t.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(t);
+            return (Some(start), Some(start + Duration::minutes(1)));
+        }
+        Unit::Hour => {
+            let start = t
+                .with_minute(0)
+                .and_then(|t| t.with_second(0))
+                .and_then(|t| t.with_nanosecond(0))
+                .unwrap_or(t);
+            return (Some(start), Some(start + Duration::hours(1)));
+        }
+        Unit::Day => (date, date + Duration::days(1)),
+        Unit::Week => {
+            let monday = last_weekday(date, Weekday::Mon);
+            (monday, monday + Duration::days(7))
+        }
+        Unit::Month => {
+            let first = date.with_day(1).unwrap_or(date);
+            (first, first + Months::new(1))
+        }
+        Unit::Year => {
+            let first = NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date);
+            (first, first + Months::new(12))
+        }
+    };
+    (Some(midnight(start)), Some(midnight(end)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2024-05-15, 14:30.
+    fn now() -> DateTime<Local> {
+        at(2024, 5, 15, 14, 30)
+    }
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> DateTime<Local> {
+        at(y, m, d, 0, 0)
+    }
+
+    fn bounds(expr: &str) -> Span {
+        let filter = TimeFilter::parse(expr, now()).unwrap_or_else(|| panic!("{expr:?} not understood"));
+        (filter.start, filter.end)
+    }
+
+    #[test]
+    fn days_relative_to_today() {
+        assert_eq!(bounds("today"), (Some(day(2024, 5, 15)), Some(day(2024, 5, 16))));
+        assert_eq!(bounds("yesterday"), (Some(day(2024, 5, 14)), Some(day(2024, 5, 15))));
+        assert_eq!(bounds("2 days ago"), (Some(day(2024, 5, 13)), Some(day(2024, 5, 14))));
+        assert_eq!(bounds("a couple of days ago"), bounds("2 days ago"));
+    }
+
+    #[test]
+    fn last_week_is_a_rolling_window() {
+        assert_eq!(bounds("last week"), (Some(at(2024, 5, 8, 14, 30)), None));
+    }
+
+    #[test]
+    fn open_ended_ranges() {
+        assert_eq!(bounds("since monday"), (Some(day(2024, 5, 13)), None));
+        assert_eq!(bounds("before 2024-03-01"), (None, Some(day(2024, 3, 1))));
+    }
+
+    #[test]
+    fn months_resolve_to_the_most_recent_one() {
+        assert_eq!(bounds("in march"), (Some(day(2024, 3, 1)), Some(day(2024, 4, 1))));
+        // June hasn't come yet this year, so it's last year's
+        assert_eq!(
+            bounds("between june and august"),
+            (Some(day(2023, 6, 1)), Some(day(2023, 9, 1)))
+        );
+    }
+
+    #[test]
+    fn field_words_pick_the_timestamp() {
+        let filter = TimeFilter::parse("created yesterday", now()).unwrap();
+        assert_eq!(filter.field, TimeField::Created);
+        assert_eq!((filter.start, filter.end), bounds("yesterday"));
+    }
+
+    #[test]
+    fn unknown_phrases_are_rejected() {
+        for expr in ["", "purple elephants", "sometime", "between june", "2024-13-45", "2024-13", "2024-0"] {
+            assert_eq!(TimeFilter::parse(expr, now()), None, "{expr:?}");
+        }
+    }
+
+    #[test]
+    fn huge_amounts_are_rejected_instead_of_panicking() {
+        for expr in [
+            "4294967295 years ago",
+            "4294967295 weeks ago",
+            "last 4294967295 days",
+            "older than 4294967295 months",
+            "within 4294967295 hours",
+        ] {
+            assert_eq!(TimeFilter::parse(expr, now()), None, "{expr:?}");
+        }
+    }
+}
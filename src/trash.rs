@@ -0,0 +1,263 @@
+use chrono::Local;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A file moved into the trash, and where its `.trashinfo` lives.
+#[derive(Debug, Clone)]
+pub struct Trashed {
+    pub original: PathBuf,
+    pub trashed: PathBuf,
+    pub info: PathBuf,
+}
+
+/// The home trash from the freedesktop.org Trash spec:
+/// `$XDG_DATA_HOME/Trash`, usually `~/.local/share/Trash`.
+pub fn trash_dir() -> anyhow::Result<PathBuf> {
+    let data = dirs::data_dir()
+        .ok_or_else(|| anyhow::anyhow!("Can't find the Trash: neither $XDG_DATA_HOME nor $HOME is set"))?;
+    Ok(data.join("Trash"))
+}
+
+/// Whether `path` is something `trash_file` takes: a file, or a symlink
+/// (which is trashed itself, never its target).
+pub fn is_trashable(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.is_file() || m.is_symlink())
+}
+
+/// Move a file to the trash, writing the `.trashinfo` first as the spec
+/// asks, so file managers can show where it came from and restore it.
+pub fn trash_file(path: &Path) -> anyhow::Result<Trashed> {
+    // Absolute but not resolved: a symlink must not be swapped for its
+    // target, and the caller's spelling is the one the index knows
+    let original = std::path::absolute(path)?;
+    let file_name = original
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Not a file: {}", path.display()))?;
+    let dir = trash_dir()?;
+    fs::create_dir_all(dir.join("files"))?;
+    fs::create_dir_all(dir.join("info"))?;
+
+    let name = file_name.to_string_lossy().to_string();
+
+    // Claim a unique name by creating its info file exclusively
+    let (stored_name, info) = (1..)
+        .map(|n| match name.rsplit_once('.') {
+            _ if n == 1 => name.clone(),
+            Some((stem, ext)) if !stem.is_empty() => format!("{}.{}.{}", stem, n, ext),
+            _ => format!("{}.{}", name, n),
+        })
+        .find_map(|candidate| {
+            let info = dir.join("info").join(format!("{}.trashinfo", candidate));
+            if present(&dir.join("files").join(&candidate)) {
+                return None;
+            }
+            match OpenOptions::new().write(true).create_new(true).open(&info) {
+                Ok(mut f) => {
+                    let body = format!(
+                        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                        percent_encode(&original.to_string_lossy()),
+                        Local::now().format("%Y-%m-%dT%H:%M:%S")
+                    );
+                    Some(f.write_all(body.as_bytes()).map(|_| (candidate, info)))
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => None,
+                Err(e) => Some(Err(e)),
+            }
+        })
+        .expect("unbounded search for a free trash name")?;
+
+    let trashed = dir.join("files").join(&stored_name);
+    if let Err(e) = move_file(&original, &trashed) {
+        let _ = fs::remove_file(&info);
+        return Err(e);
+    }
+
+    Ok(Trashed {
+        original,
+        trashed,
+        info,
+    })
+}
+
+/// Put a trashed file back where it came from.
+pub fn restore(trashed: &Path, info: &Path, original: &Path) -> anyhow::Result<()> {
+    if present(original) {
+        anyhow::bail!("{} exists again; not overwriting it", original.display());
+    }
+    if !present(trashed) {
+        anyhow::bail!("{} is no longer in the trash", trashed.display());
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    move_file(trashed, original)?;
+    let _ = fs::remove_file(info);
+    Ok(())
+}
+
+/// Rename, or copy and remove when crossing filesystems.
+pub fn move_file(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    // A link is recreated, not replaced by a copy of what it points to
+    if fs::symlink_metadata(from)?.is_symlink() {
+        copy_link(from, to)?;
+    } else {
+        fs::copy(from, to)?;
+    }
+    fs::remove_file(from)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_link(from: &Path, to: &Path) -> anyhow::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_link(from: &Path, _to: &Path) -> anyhow::Result<()> {
+    anyhow::bail!("Can't move the link {} to another drive", from.display())
+}
+
+/// Like `exists`, but true for a symlink even when its target is gone.
+fn present(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+/// Percent-encode a path for the `Path=` key (RFC 2396, keeping `/`).
+fn percent_encode(path: &str) -> String {
+    let mut out = String::new();
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, MutexGuard};
+    use tempfile::TempDir;
+
+    // XDG_DATA_HOME is process-wide, so trash tests take turns
+    static ENV: Mutex<()> = Mutex::new(());
+
+    /// A scratch folder with the home trash pointed inside it.
+    fn scratch() -> (MutexGuard<'static, ()>, TempDir) {
+        let guard = ENV.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp = TempDir::new().unwrap();
+        // SAFETY: only tests holding ENV touch the environment
+        unsafe { std::env::set_var("XDG_DATA_HOME", tmp.path().join("data")) };
+        (guard, tmp)
+    }
+
+    #[test]
+    fn percent_encodes_all_but_unreserved_bytes() {
+        assert_eq!(percent_encode("/home/me/a-b_c.~d"), "/home/me/a-b_c.~d");
+        assert_eq!(
+            percent_encode("/home/me/My Files/naïve #1.txt"),
+            "/home/me/My%20Files/na%C3%AFve%20%231.txt"
+        );
+    }
+
+    #[test]
+    fn writes_trashinfo_and_moves_the_file() {
+        let (_env, tmp) = scratch();
+        let file = tmp.path().join("tax return.pdf");
+        fs::write(&file, "x").unwrap();
+
+        let t = trash_file(&file).unwrap();
+        let trash = tmp.path().join("data/Trash");
+        assert_eq!(t.trashed, trash.join("files/tax return.pdf"));
+        assert_eq!(t.info, trash.join("info/tax return.pdf.trashinfo"));
+        assert!(!file.exists());
+        assert_eq!(fs::read_to_string(&t.trashed).unwrap(), "x");
+
+        let info = fs::read_to_string(&t.info).unwrap();
+        let lines: Vec<&str> = info.lines().collect();
+        assert_eq!(lines[0], "[Trash Info]");
+        assert_eq!(lines[1], format!("Path={}", percent_encode(&file.to_string_lossy())));
+        assert!(lines[1].ends_with("/tax%20return.pdf"));
+        let date = lines[2].strip_prefix("DeletionDate=").unwrap();
+        assert!(chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S").is_ok());
+    }
+
+    #[test]
+    fn same_name_gets_a_numbered_slot() {
+        let (_env, tmp) = scratch();
+        let mut trashed = Vec::new();
+        for dir in ["a", "b", "c"] {
+            let file = tmp.path().join(dir).join("report.pdf");
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(&file, dir).unwrap();
+            trashed.push(trash_file(&file).unwrap());
+        }
+        let names: Vec<String> = trashed
+            .iter()
+            .map(|t| t.trashed.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["report.pdf", "report.2.pdf", "report.3.pdf"]);
+        assert!(trashed[1].info.ends_with("info/report.2.pdf.trashinfo"));
+        assert_eq!(fs::read_to_string(&trashed[1].trashed).unwrap(), "b");
+
+        // A file left in files/ without its info still blocks the name
+        let trash = tmp.path().join("data/Trash");
+        fs::write(trash.join("files/notes"), "").unwrap();
+        let file = tmp.path().join("notes");
+        fs::write(&file, "").unwrap();
+        assert_eq!(trash_file(&file).unwrap().trashed, trash.join("files/notes.2"));
+    }
+
+    #[test]
+    fn restore_puts_the_file_back() {
+        let (_env, tmp) = scratch();
+        let file = tmp.path().join("sub/notes.txt");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "keep").unwrap();
+        let t = trash_file(&file).unwrap();
+        fs::remove_dir(file.parent().unwrap()).unwrap();
+
+        restore(&t.trashed, &t.info, &t.original).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "keep");
+        assert!(!t.trashed.exists());
+        assert!(!t.info.exists());
+    }
+
+    #[test]
+    fn restore_refuses_to_overwrite() {
+        let (_env, tmp) = scratch();
+        let file = tmp.path().join("notes.txt");
+        fs::write(&file, "old").unwrap();
+        let t = trash_file(&file).unwrap();
+        fs::write(&file, "new").unwrap();
+
+        let err = restore(&t.trashed, &t.info, &t.original).unwrap_err();
+        assert!(err.to_string().contains("not overwriting"));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&t.trashed).unwrap(), "old");
+        assert!(t.info.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn trashes_a_symlink_not_its_target() {
+        let (_env, tmp) = scratch();
+        let target = tmp.path().join("target.txt");
+        let link = tmp.path().join("link.txt");
+        fs::write(&target, "x").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let t = trash_file(&link).unwrap();
+        assert_eq!(t.original, link);
+        assert!(t.trashed.is_symlink());
+        assert!(target.exists());
+    }
+}
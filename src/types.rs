@@ -1,4 +1,55 @@
-#[derive(Debug, Clone)]
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Default)]
 pub struct SearchResults {
-    pub items: Vec<String>, // absolute paths
+    pub query: String,
+    pub query_vector: Vec<f32>, // kept so opened results can be learned from
+    pub model: String,
+    pub items: Vec<SearchHit>,
+}
+
+impl SearchResults {
+    /// Machine-readable results, for `meow search --json`.
+    pub fn to_json(&self) -> Value {
+        let results: Vec<Value> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, hit)| {
+                json!({
+                    "rank": i + 1,
+                    "path": hit.path,
+                    "score": hit.score,
+                    "semantic": hit.semantic,
+                    "lexical": hit.lexical,
+                    "chunk": hit.chunk.as_ref().map(|c| json!({
+                        "offset": c.offset,
+                        "score": c.score,
+                        "preview": c.preview,
+                    })),
+                })
+            })
+            .collect();
+        json!({ "query": self.query, "model": self.model, "results": results })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub path: String, // absolute path
+    pub score: f32,   // final fused score
+    pub semantic: f32,
+    pub lexical: f32, // normalized BM25, 0..1
+    pub recency: f32, // 1.0 = modified just now, halves every half-life
+    pub opens: u32,   // times opened from meow
+    pub learned: f32, // 0..1, how often chosen for similar past queries
+    pub chunk: Option<ChunkMatch>,
+}
+
+/// The part of a long document that matched best.
+#[derive(Debug, Clone)]
+pub struct ChunkMatch {
+    pub offset: usize,
+    pub score: f32,
+    pub preview: String,
 }
@@ -1,10 +1,61 @@
 use rusqlite::{params, Connection};
-use bincode;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
 pub struct VectorDB {
     conn: Connection,
 }
 
+/// What we remember about a file on disk to tell whether it changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileState {
+    pub modified: i64,
+    pub size: i64,
+}
+
+/// Which model and text format produced the stored vectors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexMeta {
+    pub model: String,
+    pub dimension: usize,
+    pub repr_version: u32,
+}
+
+/// An indexed file as stored in the `embeddings` table.
+#[derive(Debug, Clone)]
+pub struct FileRecord {
+    pub state: FileState,
+    pub hash: Option<String>, // content fingerprint, for move detection
+}
+
+/// A result the user opened, with the embedding of the query that found it.
+#[derive(Debug, Clone)]
+pub struct FeedbackRow {
+    pub query_vector: Vec<f32>,
+    pub path: String,
+}
+
+/// A file operation, kept so its batch can be undone.
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub action: String,        // "trash", "move", "copy" or "rename"
+    pub source: String,        // where the file was
+    pub dest: String,          // where it went (or the copy)
+    pub extra: Option<String>, // the .trashinfo file for "trash", "size mtime" of a copy
+}
+
+/// When a batch ran, and its entries still to undo, keyed by journal id.
+pub type OpenBatch = (i64, Vec<(i64, JournalEntry)>);
+
+/// One embedded slice of a longer document.
+#[derive(Debug, Clone)]
+pub struct ChunkRow {
+    pub path: String,
+    pub offset: usize, // char offset of the chunk within the extracted text
+    pub vector: Vec<f32>,
+    pub preview: String,
+}
+
 impl VectorDB {
     pub fn new(path: &str) -> anyhow::Result<Self> {
         let conn = Connection::open(path)?;
@@ -14,32 +65,386 @@ impl VectorDB {
                 path TEXT PRIMARY KEY,
                 vector BLOB,
                 modified INTEGER
+            );
+            CREATE TABLE IF NOT EXISTS chunks (
+                path TEXT NOT NULL,
+                chunk_offset INTEGER NOT NULL,
+                vector BLOB,
+                preview TEXT,
+                PRIMARY KEY (path, chunk_offset)
+            );
+            CREATE TABLE IF NOT EXISTS meta (
+                key TEXT PRIMARY KEY,
+                value TEXT
+            );
+            CREATE TABLE IF NOT EXISTS ivf_centroids (
+                id INTEGER PRIMARY KEY,
+                vector BLOB
+            );
+            CREATE TABLE IF NOT EXISTS opens (
+                path TEXT PRIMARY KEY,
+                count INTEGER NOT NULL,
+                last_opened INTEGER
+            );
+            CREATE TABLE IF NOT EXISTS feedback (
+                id INTEGER PRIMARY KEY,
+                query TEXT NOT NULL,
+                query_vector BLOB NOT NULL,
+                model TEXT NOT NULL,
+                path TEXT NOT NULL,
+                rank INTEGER NOT NULL,
+                opened_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS summaries (
+                path TEXT PRIMARY KEY,
+                modified INTEGER NOT NULL,
+                summary TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS journal (
+                id INTEGER PRIMARY KEY,
+                batch INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                source TEXT NOT NULL,
+                dest TEXT NOT NULL,
+                extra TEXT,
+                at INTEGER NOT NULL,
+                undone INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE IF NOT EXISTS fts_rows (
+                path TEXT PRIMARY KEY,
+                id INTEGER NOT NULL
+            );
+            CREATE VIRTUAL TABLE IF NOT EXISTS fts USING fts5 (
+                path UNINDEXED,
+                name,
+                location,
+                body
             );"
         )?;
 
+        // Databases created before incremental indexing lack `size`
+        ensure_column(&conn, "embeddings", "size", "INTEGER")?;
+        ensure_column(&conn, "embeddings", "hash", "TEXT")?;
+        // pdf, image, text, ... (see file_type::CATEGORIES)
+        ensure_column(&conn, "embeddings", "category", "TEXT")?;
+        // IVF cluster each vector belongs to; NULL until assigned
+        ensure_column(&conn, "embeddings", "cluster", "INTEGER")?;
+        ensure_column(&conn, "chunks", "cluster", "INTEGER")?;
+        // FTS5 can't index `path`, so rows are found through fts_rows; fill it
+        // for keyword indexes built before it existed
+        conn.execute_batch(
+            "INSERT OR IGNORE INTO fts_rows (path, id)
+             SELECT path, rowid FROM fts WHERE NOT EXISTS (SELECT 1 FROM fts_rows);",
+        )?;
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS embeddings_cluster ON embeddings (cluster);
+             CREATE INDEX IF NOT EXISTS chunks_cluster ON chunks (cluster);",
+        )?;
+
         Ok(Self { conn })
     }
 
-    pub fn store_embedding(&self, file_path: &str, vector: &[f32], modified: i64) -> anyhow::Result<()> {
+    pub fn get_meta(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let mut stmt = self.conn.prepare("SELECT value FROM meta WHERE key = ?1")?;
+        let mut rows = stmt.query_map(params![key], |row| row.get::<_, String>(0))?;
+        Ok(rows.next().transpose()?)
+    }
+
+    pub fn set_meta(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn load_meta(&self) -> anyhow::Result<Option<IndexMeta>> {
+        let (Some(model), Some(dimension), Some(repr_version)) = (
+            self.get_meta("model")?,
+            self.get_meta("dimension")?,
+            self.get_meta("repr_version")?,
+        ) else {
+            return Ok(None);
+        };
+
+        Ok(Some(IndexMeta {
+            model,
+            dimension: dimension.parse()?,
+            repr_version: repr_version.parse()?,
+        }))
+    }
+
+    pub fn store_meta(&self, meta: &IndexMeta) -> anyhow::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (key, value) in [
+            ("model", meta.model.clone()),
+            ("dimension", meta.dimension.to_string()),
+            ("repr_version", meta.repr_version.to_string()),
+        ] {
+            tx.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Length of some stored vector, for databases that predate `meta`.
+    pub fn sample_dimension(&self) -> anyhow::Result<Option<usize>> {
+        let mut stmt = self.conn.prepare("SELECT vector FROM embeddings LIMIT 1")?;
+        let mut rows = stmt.query_map([], |row| row.get::<_, Vec<u8>>(0))?;
+
+        match rows.next().transpose()? {
+            Some(blob) => Ok(Some(bincode::deserialize::<Vec<f32>>(&blob)?.len())),
+            None => Ok(None),
+        }
+    }
+
+    /// Drop every stored vector (used when rebuilding with a new model),
+    /// and learned choices, whose query vectors came from the old model.
+    pub fn clear(&self) -> anyhow::Result<()> {
+        self.conn.execute_batch(
+            "DELETE FROM embeddings;
+             DELETE FROM chunks;
+             DELETE FROM meta;
+             DELETE FROM ivf_centroids;
+             DELETE FROM fts;
+             DELETE FROM fts_rows;
+             DELETE FROM feedback;",
+        )?;
+        Ok(())
+    }
+
+    /// Forget opens, learned choices and summaries of files no longer indexed.
+    pub fn prune_orphans(&self) -> anyhow::Result<usize> {
+        let mut removed = 0;
+        for table in ["opens", "feedback", "summaries"] {
+            removed += self.conn.execute(
+                &format!("DELETE FROM {table} WHERE path NOT IN (SELECT path FROM embeddings)"),
+                [],
+            )?;
+        }
+        Ok(removed)
+    }
+
+    pub fn store_embedding(
+        &self,
+        file_path: &str,
+        vector: &[f32],
+        state: FileState,
+        hash: &str,
+    ) -> anyhow::Result<()> {
         let blob = bincode::serialize(vector)?;
 
         self.conn.execute(
-            "INSERT OR REPLACE INTO embeddings (path, vector, modified)
-             VALUES (?1, ?2, ?3)",
-            params![file_path, blob, modified],
+            "INSERT OR REPLACE INTO embeddings (path, vector, modified, size, hash)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![file_path, blob, state.modified, state.size, hash],
         )?;
         Ok(())
     }
 
-    pub fn load_all(&self) -> anyhow::Result<Vec<(String, Vec<f32>)>> {
+    /// Modification time, size and hash of every indexed file, keyed by path.
+    pub fn load_file_records(&self) -> anyhow::Result<HashMap<String, FileRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, modified, size, hash FROM embeddings")?;
+
+        let rows = stmt.query_map([], |row| {
+            let path: String = row.get(0)?;
+            let record = FileRecord {
+                state: FileState {
+                    modified: row.get::<_, Option<i64>>(1)?.unwrap_or(0),
+                    size: row.get::<_, Option<i64>>(2)?.unwrap_or(-1),
+                },
+                hash: row.get(3)?,
+            };
+            Ok((path, record))
+        })?;
+
+        let mut results = HashMap::new();
+        for r in rows {
+            let (path, record) = r?;
+            results.insert(path, record);
+        }
+        Ok(results)
+    }
+
+    pub fn file_record(&self, file_path: &str) -> anyhow::Result<Option<FileRecord>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT path, vector FROM embeddings")?;
+            .prepare("SELECT modified, size, hash FROM embeddings WHERE path = ?1")?;
+
+        let mut rows = stmt.query_map(params![file_path], |row| {
+            Ok(FileRecord {
+                state: FileState {
+                    modified: row.get::<_, Option<i64>>(0)?.unwrap_or(0),
+                    size: row.get::<_, Option<i64>>(1)?.unwrap_or(-1),
+                },
+                hash: row.get(2)?,
+            })
+        })?;
+
+        Ok(rows.next().transpose()?)
+    }
+
+    pub fn set_hash(&self, file_path: &str, hash: &str) -> anyhow::Result<()> {
+        self.conn.execute(
+            "UPDATE embeddings SET hash = ?2 WHERE path = ?1",
+            params![file_path, hash],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_category(&self, file_path: &str, category: &str) -> anyhow::Result<()> {
+        self.conn.execute(
+            "UPDATE embeddings SET category = ?2 WHERE path = ?1",
+            params![file_path, category],
+        )?;
+        Ok(())
+    }
+
+    /// Category of every indexed file; files indexed before categories
+    /// existed map to `None`.
+    pub fn load_categories(&self) -> anyhow::Result<HashMap<String, Option<String>>> {
+        let mut stmt = self.conn.prepare("SELECT path, category FROM embeddings")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+
+        let mut results = HashMap::new();
+        for r in rows {
+            let (path, category) = r?;
+            results.insert(path, category);
+        }
+        Ok(results)
+    }
+
+    /// Point an existing row (and its chunks) at a file's new location.
+    pub fn rename_path(&self, old_path: &str, new_path: &str, state: FileState) -> anyhow::Result<()> {
+        if old_path == new_path {
+            return Ok(());
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        // Rows left from a file that used to live at the new path (they're
+        // only pruned on `index`) would clash with the primary keys
+        for table in ["embeddings", "chunks", "opens", "feedback", "summaries"] {
+            tx.execute(&format!("DELETE FROM {} WHERE path = ?1", table), params![new_path])?;
+        }
+        tx.execute(
+            "UPDATE embeddings SET path = ?2, modified = ?3, size = ?4 WHERE path = ?1",
+            params![old_path, new_path, state.modified, state.size],
+        )?;
+        tx.execute(
+            "UPDATE chunks SET path = ?2 WHERE path = ?1",
+            params![old_path, new_path],
+        )?;
+        delete_text(&tx, new_path)?;
+        tx.execute(
+            "UPDATE fts SET path = ?2, name = ?3, location = ?4
+             WHERE rowid IN (SELECT id FROM fts_rows WHERE path = ?1)",
+            params![old_path, new_path, text_name(new_path), text_location(new_path)],
+        )?;
+        tx.execute(
+            "UPDATE fts_rows SET path = ?2 WHERE path = ?1",
+            params![old_path, new_path],
+        )?;
+        tx.execute(
+            "UPDATE opens SET path = ?2 WHERE path = ?1",
+            params![old_path, new_path],
+        )?;
+        tx.execute(
+            "UPDATE feedback SET path = ?2 WHERE path = ?1",
+            params![old_path, new_path],
+        )?;
+        tx.execute(
+            "UPDATE summaries SET path = ?2 WHERE path = ?1",
+            params![old_path, new_path],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Index a copy of a file under its new path by duplicating its rows.
+    pub fn copy_path(&self, old_path: &str, new_path: &str, state: FileState) -> anyhow::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO embeddings (path, vector, modified, size, hash, category, cluster)
+             SELECT ?2, vector, ?3, ?4, hash, category, cluster FROM embeddings WHERE path = ?1",
+            params![old_path, new_path, state.modified, state.size],
+        )?;
+        tx.execute("DELETE FROM chunks WHERE path = ?1", params![new_path])?;
+        tx.execute(
+            "INSERT OR REPLACE INTO chunks (path, chunk_offset, vector, preview, cluster)
+             SELECT ?2, chunk_offset, vector, preview, cluster FROM chunks WHERE path = ?1",
+            params![old_path, new_path],
+        )?;
+        delete_text(&tx, new_path)?;
+        let copied = tx.execute(
+            "INSERT INTO fts (path, name, location, body)
+             SELECT ?2, ?3, ?4, body FROM fts WHERE rowid IN (SELECT id FROM fts_rows WHERE path = ?1)",
+            params![old_path, new_path, text_name(new_path), text_location(new_path)],
+        )?;
+        if copied > 0 {
+            tx.execute(
+                "INSERT INTO fts_rows (path, id) VALUES (?1, last_insert_rowid())",
+                params![new_path],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Forget a file, or every file below a directory. Returns rows removed.
+    pub fn delete_under(&self, path: &str) -> anyhow::Result<usize> {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        let tx = self.conn.unchecked_transaction()?;
+        let removed = tx.execute(
+            "DELETE FROM embeddings WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2",
+            params![path, prefix],
+        )?;
+        tx.execute(
+            "DELETE FROM chunks WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2",
+            params![path, prefix],
+        )?;
+        tx.execute(
+            "DELETE FROM fts WHERE rowid IN (
+                 SELECT id FROM fts_rows WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2
+             )",
+            params![path, prefix],
+        )?;
+        tx.execute(
+            "DELETE FROM fts_rows WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2",
+            params![path, prefix],
+        )?;
+        tx.execute(
+            "DELETE FROM summaries WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2",
+            params![path, prefix],
+        )?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Forget everything stored for a file.
+    pub fn delete_path(&self, file_path: &str) -> anyhow::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM embeddings WHERE path = ?1", params![file_path])?;
+        tx.execute("DELETE FROM chunks WHERE path = ?1", params![file_path])?;
+        delete_text(&tx, file_path)?;
+        tx.execute("DELETE FROM summaries WHERE path = ?1", params![file_path])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Load file vectors, optionally only those in the given IVF clusters
+    /// (plus any not assigned to a cluster yet).
+    pub fn load_all(&self, clusters: Option<&[i64]>) -> anyhow::Result<Vec<(String, Vec<f32>)>> {
+        let sql = format!("SELECT path, vector FROM embeddings{}", cluster_filter(clusters));
+        let mut stmt = self.conn.prepare(&sql)?;
 
         let rows = stmt.query_map([], |row| {
             let path: String = row.get(0)?;
             let blob: Vec<u8> = row.get(1)?;
-            let vector: Vec<f32> = bincode::deserialize(&blob).unwrap();
+            let vector: Vec<f32> = bincode::deserialize(&blob).unwrap_or_default();
             Ok((path, vector))
         })?;
 
@@ -49,4 +454,422 @@ impl VectorDB {
         }
         Ok(results)
     }
+
+    /// Replace all chunks stored for `file_path` with `chunks`.
+    pub fn store_chunks(&self, file_path: &str, chunks: &[ChunkRow]) -> anyhow::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        tx.execute("DELETE FROM chunks WHERE path = ?1", params![file_path])?;
+
+        for chunk in chunks {
+            let blob = bincode::serialize(&chunk.vector)?;
+            tx.execute(
+                "INSERT INTO chunks (path, chunk_offset, vector, preview)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![file_path, chunk.offset as i64, blob, chunk.preview],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn load_chunks(&self, clusters: Option<&[i64]>) -> anyhow::Result<Vec<ChunkRow>> {
+        let sql = format!(
+            "SELECT path, chunk_offset, vector, preview FROM chunks{}",
+            cluster_filter(clusters)
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+
+        let rows = stmt.query_map([], |row| {
+            let blob: Vec<u8> = row.get(2)?;
+            Ok(ChunkRow {
+                path: row.get(0)?,
+                offset: row.get::<_, i64>(1)? as usize,
+                vector: bincode::deserialize(&blob).unwrap_or_default(),
+                preview: row.get(3)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for r in rows {
+            results.push(r?);
+        }
+        Ok(results)
+    }
+
+    // ---- Usage ----
+
+    /// Remember that the user opened `file_path` from the results.
+    pub fn record_open(&self, file_path: &str, when: i64) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT INTO opens (path, count, last_opened) VALUES (?1, 1, ?2)
+             ON CONFLICT(path) DO UPDATE SET count = count + 1, last_opened = ?2",
+            params![file_path, when],
+        )?;
+        Ok(())
+    }
+
+    /// How often each file has been opened.
+    pub fn load_open_counts(&self) -> anyhow::Result<HashMap<String, u32>> {
+        let mut stmt = self.conn.prepare("SELECT path, count FROM opens")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u32))
+        })?;
+
+        let mut results = HashMap::new();
+        for r in rows {
+            let (path, count) = r?;
+            results.insert(path, count);
+        }
+        Ok(results)
+    }
+
+    /// Log that the result at `rank` (1-based) was opened for `query`.
+    pub fn record_feedback(
+        &self,
+        query: &str,
+        query_vector: &[f32],
+        model: &str,
+        file_path: &str,
+        rank: usize,
+        when: i64,
+    ) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT INTO feedback (query, query_vector, model, path, rank, opened_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![query, bincode::serialize(query_vector)?, model, file_path, rank as i64, when],
+        )?;
+        Ok(())
+    }
+
+    /// Past choices whose query was embedded with `model`.
+    pub fn load_feedback(&self, model: &str) -> anyhow::Result<Vec<FeedbackRow>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT query_vector, path FROM feedback WHERE model = ?1")?;
+        let rows = stmt.query_map(params![model], |row| {
+            Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut results = Vec::new();
+        for r in rows {
+            let (blob, path) = r?;
+            results.push(FeedbackRow {
+                query_vector: bincode::deserialize(&blob).unwrap_or_default(),
+                path,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Learned (query, path) pairs with how often and how recently each was chosen.
+    pub fn feedback_summary(&self) -> anyhow::Result<Vec<(String, String, u32, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT query, path, COUNT(*), MAX(opened_at) FROM feedback
+             GROUP BY query, path ORDER BY MAX(opened_at) DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get::<_, i64>(2)? as u32, row.get(3)?))
+        })?;
+
+        let mut results = Vec::new();
+        for r in rows {
+            results.push(r?);
+        }
+        Ok(results)
+    }
+
+    /// Forget everything learned from opened results. Returns choices removed.
+    pub fn clear_feedback(&self) -> anyhow::Result<usize> {
+        let removed = self.conn.execute("DELETE FROM feedback", [])?;
+        self.conn.execute("DELETE FROM opens", [])?;
+        Ok(removed)
+    }
+
+    // ---- Summaries ----
+
+    /// A cached summary, if the file hasn't changed since it was written.
+    pub fn load_summary(&self, file_path: &str, modified: i64) -> anyhow::Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT summary FROM summaries WHERE path = ?1 AND modified = ?2")?;
+        let mut rows = stmt.query(params![file_path, modified])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn store_summary(&self, file_path: &str, modified: i64, summary: &str) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO summaries (path, modified, summary) VALUES (?1, ?2, ?3)",
+            params![file_path, modified, summary],
+        )?;
+        Ok(())
+    }
+
+    // ---- Journal ----
+
+    /// Log a batch of file operations together. Returns the batch number.
+    pub fn log_batch(&self, entries: &[JournalEntry], when: i64) -> anyhow::Result<i64> {
+        let tx = self.conn.unchecked_transaction()?;
+        let batch: i64 = tx.query_row("SELECT COALESCE(MAX(batch), 0) + 1 FROM journal", [], |row| {
+            row.get(0)
+        })?;
+        for e in entries {
+            tx.execute(
+                "INSERT INTO journal (batch, action, source, dest, extra, at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![batch, e.action, e.source, e.dest, e.extra, when],
+            )?;
+        }
+        tx.commit()?;
+        Ok(batch)
+    }
+
+    /// The most recent batch with entries not yet undone (only those).
+    pub fn last_batch(&self) -> anyhow::Result<Option<OpenBatch>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, at, action, source, dest, extra FROM journal
+             WHERE undone = 0 AND batch = (SELECT MAX(batch) FROM journal WHERE undone = 0)
+             ORDER BY id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                JournalEntry {
+                    action: row.get(2)?,
+                    source: row.get(3)?,
+                    dest: row.get(4)?,
+                    extra: row.get(5)?,
+                },
+            ))
+        })?;
+
+        let mut at = None;
+        let mut entries = Vec::new();
+        for r in rows {
+            let (id, when, entry) = r?;
+            at = Some(when);
+            entries.push((id, entry));
+        }
+        Ok(at.map(|at| (at, entries)))
+    }
+
+    /// Record that one journal entry was reversed.
+    pub fn mark_undone(&self, id: i64) -> anyhow::Result<()> {
+        self.conn
+            .execute("UPDATE journal SET undone = 1 WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // ---- Keyword (FTS5) index ----
+
+    /// Store the searchable text of a file: its name, folders and body.
+    pub fn store_text(&self, file_path: &str, body: Option<&str>) -> anyhow::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        delete_text(&tx, file_path)?;
+        tx.execute(
+            "INSERT INTO fts (path, name, location, body) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                file_path,
+                text_name(file_path),
+                text_location(file_path),
+                body.unwrap_or("")
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO fts_rows (path, id) VALUES (?1, last_insert_rowid())",
+            params![file_path],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Paths that already have a keyword-index row.
+    pub fn text_paths(&self) -> anyhow::Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare("SELECT path FROM fts_rows")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut results = HashSet::new();
+        for r in rows {
+            results.insert(r?);
+        }
+        Ok(results)
+    }
+
+    /// BM25 keyword search. Returns (path, score) with higher = better.
+    ///
+    /// Terms are OR-ed so partial matches still rank; file name hits weigh
+    /// most, then folders, then body text.
+    pub fn search_text(&self, terms: &[String], limit: usize) -> anyhow::Result<Vec<(String, f32)>> {
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Quote every term so user input can't inject FTS syntax
+        let query = terms
+            .iter()
+            .map(|t| format!("\"{}\"", t.replace('"', "")))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+        let mut stmt = self.conn.prepare(
+            "SELECT path, -bm25(fts, 0.0, 10.0, 3.0, 1.0) FROM fts
+             WHERE fts MATCH ?1 ORDER BY bm25(fts, 0.0, 10.0, 3.0, 1.0) LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![query, limit as i64], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)? as f32))
+        })?;
+
+        let mut results = Vec::new();
+        for r in rows {
+            results.push(r?);
+        }
+        Ok(results)
+    }
+
+    // ---- IVF (approximate search) storage ----
+
+    pub fn load_centroids(&self) -> anyhow::Result<Vec<Vec<f32>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT vector FROM ivf_centroids ORDER BY id")?;
+
+        let rows = stmt.query_map([], |row| row.get::<_, Vec<u8>>(0))?;
+
+        let mut results = Vec::new();
+        for r in rows {
+            results.push(bincode::deserialize(&r?)?);
+        }
+        Ok(results)
+    }
+
+    /// Replace the centroids and mark every vector as unassigned.
+    pub fn store_centroids(&self, centroids: &[Vec<f32>]) -> anyhow::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM ivf_centroids", [])?;
+        for (id, c) in centroids.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO ivf_centroids (id, vector) VALUES (?1, ?2)",
+                params![id as i64, bincode::serialize(c)?],
+            )?;
+        }
+        tx.execute("UPDATE embeddings SET cluster = NULL", [])?;
+        tx.execute("UPDATE chunks SET cluster = NULL", [])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Total number of file and chunk vectors.
+    pub fn count_vectors(&self) -> anyhow::Result<usize> {
+        let n: i64 = self.conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM embeddings) + (SELECT COUNT(*) FROM chunks)",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(n as usize)
+    }
+
+    /// A random sample of file and chunk vectors, for training centroids.
+    pub fn sample_vectors(&self, limit: usize) -> anyhow::Result<Vec<Vec<f32>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT vector FROM (
+                 SELECT vector FROM embeddings UNION ALL SELECT vector FROM chunks
+             ) ORDER BY random() LIMIT ?1",
+        )?;
+
+        let rows = stmt.query_map(params![limit as i64], |row| row.get::<_, Vec<u8>>(0))?;
+
+        let mut results = Vec::new();
+        for r in rows {
+            results.push(bincode::deserialize(&r?)?);
+        }
+        Ok(results)
+    }
+
+    /// Up to `limit` vectors not yet assigned to a cluster, as (table, rowid, vector).
+    pub fn load_unclustered(&self, limit: usize) -> anyhow::Result<Vec<(&'static str, i64, Vec<f32>)>> {
+        let mut results = Vec::new();
+
+        for table in ["embeddings", "chunks"] {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT rowid, vector FROM {} WHERE cluster IS NULL LIMIT ?1",
+                table
+            ))?;
+            let rows = stmt.query_map(params![limit as i64], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?;
+            for r in rows {
+                let (rowid, blob) = r?;
+                results.push((table, rowid, bincode::deserialize(&blob)?));
+            }
+        }
+        Ok(results)
+    }
+
+    pub fn set_clusters(&self, assignments: &[(&str, i64, i64)]) -> anyhow::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (table, rowid, cluster) in assignments {
+            tx.execute(
+                &format!("UPDATE {} SET cluster = ?2 WHERE rowid = ?1", table),
+                params![rowid, cluster],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn text_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn text_location(path: &str) -> String {
+    Path::new(path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// `WHERE` clause restricting rows to the probed clusters (ids are integers,
+/// so inlining them is safe).
+fn cluster_filter(clusters: Option<&[i64]>) -> String {
+    match clusters {
+        None => String::new(),
+        Some(ids) => {
+            let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+            format!(" WHERE cluster IS NULL OR cluster IN ({})", ids.join(","))
+        }
+    }
+}
+
+/// Remove a file's keyword-index row by rowid (a `WHERE path =` on the
+/// FTS table would scan all of it).
+fn delete_text(conn: &Connection, file_path: &str) -> anyhow::Result<()> {
+    conn.execute(
+        "DELETE FROM fts WHERE rowid IN (SELECT id FROM fts_rows WHERE path = ?1)",
+        params![file_path],
+    )?;
+    conn.execute("DELETE FROM fts_rows WHERE path = ?1", params![file_path])?;
+    Ok(())
+}
+
+/// Add `column` to `table` if an older database doesn't have it yet.
+fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> anyhow::Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .flatten()
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl))?;
+    }
+    Ok(())
 }
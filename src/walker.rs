@@ -0,0 +1,144 @@
+use crate::config::{Config, WalkOptions};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use std::path::{Path, PathBuf};
+
+/// Per-directory ignore file, gitignore syntax.
+const IGNORE_FILENAME: &str = ".meowignore";
+
+/// Collect all files under `root`, honoring `.meowignore`, `.gitignore`,
+/// the global ignore file and the walk limits from the config. Large files
+/// are included; `max_file_size` only limits reading their contents.
+pub fn walk_files(root: &Path, opts: &WalkOptions) -> Vec<PathBuf> {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(!opts.include_hidden)
+        .git_ignore(opts.respect_gitignore)
+        .git_global(opts.respect_gitignore)
+        .git_exclude(opts.respect_gitignore)
+        .require_git(false) // .gitignore counts even outside a git checkout
+        .parents(true)
+        .max_depth(opts.max_depth)
+        .add_custom_ignore_filename(IGNORE_FILENAME);
+
+    let global = global_ignore_path();
+    if global.exists()
+        && let Some(e) = builder.add_ignore(&global)
+    {
+        eprintln!("Could not read {}: {}", global.display(), e);
+    }
+
+    let excluded = opts.exclude_dirs.clone();
+    builder.filter_entry(move |entry| {
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+        !(is_dir && excluded.iter().any(|d| entry.file_name() == d.as_str()))
+    });
+
+    builder
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// `~/.config/meow/ignore`, applied to every root.
+pub fn global_ignore_path() -> PathBuf {
+    Config::path().with_file_name("ignore")
+}
+
+/// Cheap per-path version of the walker's rules, for paths reported by the
+/// file watcher. Checks hidden components, excluded folders, depth and the
+/// ignore files collected in `rules`.
+pub fn is_excluded(path: &Path, root: &Path, opts: &WalkOptions, rules: &IgnoreRules) -> bool {
+    let Ok(rel) = path.strip_prefix(root) else {
+        return true;
+    };
+
+    let names: Vec<&str> = rel.iter().filter_map(|c| c.to_str()).collect();
+
+    if !opts.include_hidden && names.iter().any(|n| n.starts_with('.')) {
+        return true;
+    }
+    if names.iter().any(|n| opts.exclude_dirs.iter().any(|d| d == n)) {
+        return true;
+    }
+    if opts.max_depth.is_some_and(|max| names.len() > max) {
+        return true;
+    }
+    rules.is_ignored(path, path.is_dir())
+}
+
+/// The `.gitignore`, `.meowignore` and global ignore files that apply under
+/// one root, so single paths can be checked the way the walker would.
+pub struct IgnoreRules {
+    /// Shallowest first; a deeper file overrides (or re-includes) a shallower one.
+    matchers: Vec<Gitignore>,
+}
+
+impl IgnoreRules {
+    pub fn load(root: &Path, opts: &WalkOptions) -> Self {
+        let mut matchers = Vec::new();
+
+        let global = global_ignore_path();
+        if global.exists() {
+            matchers.push(build_matcher(root, &global));
+        }
+
+        let mut names = vec![IGNORE_FILENAME];
+        if opts.respect_gitignore {
+            names.push(".gitignore");
+        }
+
+        // Ignore files are hidden, so walk with hidden files on just to find them
+        let mut files: Vec<PathBuf> = WalkBuilder::new(root)
+            .hidden(false)
+            .git_ignore(opts.respect_gitignore)
+            .require_git(false)
+            .max_depth(opts.max_depth)
+            .add_custom_ignore_filename(IGNORE_FILENAME)
+            .build()
+            .flatten()
+            .filter(|e| e.file_name().to_str().is_some_and(|n| names.contains(&n)))
+            .map(|e| e.into_path())
+            .collect();
+        files.sort_by_key(|f| f.components().count());
+
+        for file in files {
+            let dir = file.parent().unwrap_or(root);
+            matchers.push(build_matcher(dir, &file));
+        }
+        Self { matchers }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for m in &self.matchers {
+            if !path.starts_with(m.path()) {
+                continue;
+            }
+            let found = m.matched_path_or_any_parents(path, is_dir);
+            if found.is_ignore() {
+                ignored = true;
+            } else if found.is_whitelist() {
+                ignored = false;
+            }
+        }
+        ignored
+    }
+
+    /// Whether a change to `path` means the rules must be reloaded.
+    pub fn is_rules_file(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n == IGNORE_FILENAME || n == ".gitignore")
+    }
+}
+
+fn build_matcher(dir: &Path, file: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(file) {
+        eprintln!("Could not read {}: {}", file.display(), e);
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
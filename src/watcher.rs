@@ -0,0 +1,154 @@
+use crate::ann;
+use crate::config::{Config, WalkOptions};
+use crate::embedding::{self, Embedder};
+use crate::indexer::{check_index_meta, file_state, index_file};
+use crate::vector_db::VectorDB;
+use crate::walker::{is_excluded, walk_files, IgnoreRules};
+use notify::{RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// How long a path must stay quiet before we (re)index it. Editors and
+/// downloads emit bursts of events for a single save.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+const DB_FILE: &str = "meow_vectors.db";
+
+pub fn run_watcher(roots: &[PathBuf], config: &Config) -> anyhow::Result<()> {
+    if roots.is_empty() {
+        println!("No index roots found. Add one with `roots add <dir>` or `meow watch --root <dir>`.");
+        return Ok(());
+    }
+
+    let db = VectorDB::new(DB_FILE)?;
+    let embedder = embedding::from_config(&config.embedding)?;
+    check_index_meta(&db, embedder.as_ref())?;
+
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for root in roots {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+        println!("👀 Watching {}", root.display());
+    }
+    println!("Keeping the index live. Press Ctrl+C to stop.");
+
+    let mut rules: HashMap<PathBuf, IgnoreRules> = roots
+        .iter()
+        .map(|r| (r.clone(), IgnoreRules::load(r, &config.walk)))
+        .collect();
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+
+    loop {
+        match rx.recv_timeout(Duration::from_millis(500)) {
+            Ok(Ok(event)) => {
+                if event.kind.is_access() {
+                    continue;
+                }
+                for path in event.paths {
+                    // Our own DB writes would otherwise retrigger indexing forever
+                    if is_own_db(&path) {
+                        continue;
+                    }
+                    pending.insert(path, Instant::now());
+                }
+            }
+            Ok(Err(e)) => eprintln!("Watch error: {e}"),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let now = Instant::now();
+        let settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, seen)| now.duration_since(**seen) >= DEBOUNCE)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in settled {
+            pending.remove(&path);
+
+            let Some(root) = roots.iter().find(|r| path.starts_with(r)) else {
+                continue;
+            };
+            // Edited ignore files take effect for the next events
+            if IgnoreRules::is_rules_file(&path) {
+                rules.insert(root.clone(), IgnoreRules::load(root, &config.walk));
+                println!("🔁 Reloaded ignore rules from {}", path.display());
+                continue;
+            }
+            let root_rules = &rules[root];
+            if let Err(e) = sync_path(&db, embedder.as_ref(), &path, root, &config.walk, root_rules) {
+                eprintln!("Skipped {}: {}", path.display(), e);
+            }
+            if let Err(e) = ann::assign_unclustered(&db) {
+                eprintln!("Could not update approximate index: {e}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_own_db(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with(DB_FILE))
+}
+
+/// Bring the index in line with whatever is at `path` now.
+fn sync_path(
+    db: &VectorDB,
+    embedder: &dyn Embedder,
+    path: &Path,
+    root: &Path,
+    walk: &WalkOptions,
+    rules: &IgnoreRules,
+) -> anyhow::Result<()> {
+    let key = path.to_string_lossy();
+
+    // Removed (or moved away): drop the file, or everything under a folder
+    if !path.exists() {
+        let removed = db.delete_under(&key)?;
+        if removed > 0 {
+            println!("➖ {} ({} removed)", path.display(), removed);
+        }
+        return Ok(());
+    }
+
+    if is_excluded(path, root, walk, rules) {
+        return Ok(());
+    }
+
+    // A folder appeared (e.g. moved in): index what's inside
+    if path.is_dir() {
+        for file in walk_files(path, walk) {
+            // One unreadable file shouldn't stop the rest of the folder
+            if let Err(e) = index_if_changed(db, embedder, &file, walk.max_file_size) {
+                eprintln!("Skipped {}: {}", file.display(), e);
+            }
+        }
+        return Ok(());
+    }
+
+    index_if_changed(db, embedder, path, walk.max_file_size)
+}
+
+fn index_if_changed(
+    db: &VectorDB,
+    embedder: &dyn Embedder,
+    path: &Path,
+    max_size: Option<u64>,
+) -> anyhow::Result<()> {
+    let state = file_state(path)?;
+    let key = path.to_string_lossy();
+
+    if db.file_record(&key)?.is_some_and(|r| r.state == state) {
+        return Ok(());
+    }
+
+    index_file(db, embedder, path, max_size)?;
+    println!("➕ {}", path.display());
+    Ok(())
+}